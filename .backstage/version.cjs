@@ -3,6 +3,7 @@ const path = require("path");
 
 const version = process.env.GIT_VERSION;
 const version_re = /"?version"?\s*[=:]\s*"0.0.0"/;
+const web_dependency_re = /^(pagefind_web\s*=\s*\{\s*version\s*=\s*)"0.0.0"/m;
 
 const err = (m) => {
     console.error(m);
@@ -21,6 +22,8 @@ const file = (localPath) => {
 
 let pagefindCfg = file("../pagefind/Cargo.toml");
 pagefindCfg.contents = pagefindCfg.contents.replace(version_re, `version = "${version}"`);
+if (!web_dependency_re.test(pagefindCfg.contents)) err(`Expected ${pagefindCfg.path} to depend on pagefind_web with a version of "0.0.0"`);
+pagefindCfg.contents = pagefindCfg.contents.replace(web_dependency_re, `$1"${version}"`);
 fs.writeFileSync(pagefindCfg.path, pagefindCfg.contents);
 
 let pagefindWebCfg = file("../pagefind_web/Cargo.toml");
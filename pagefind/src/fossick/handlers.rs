@@ -0,0 +1,133 @@
+//! Chooses how each file found while walking the site is read.
+
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{bail, Error};
+
+use crate::SearchOptions;
+
+/// How a file is read into the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileHandler {
+    /// Parsed as an HTML page
+    Html,
+    /// Rendered from Markdown, with its front matter as metadata
+    Markdown,
+    /// Indexed as plain text, split into paragraphs on blank lines
+    Text,
+    /// RSS and Atom feeds are indexed as a page for each entry,
+    /// and other XML files as a single page of their text
+    Xml,
+}
+
+impl FileHandler {
+    /// Returns the handler for a file, using the `file_handlers` option
+    /// before the built-in handlers for each extension.
+    pub fn for_path(path: &Path, options: &SearchOptions) -> Self {
+        let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+            return Self::Html;
+        };
+        let extension = extension.to_ascii_lowercase();
+
+        if let Some(handler) = options.file_handlers.get(&extension) {
+            return *handler;
+        }
+
+        match extension.as_str() {
+            "md" | "markdown" => Self::Markdown,
+            "txt" => Self::Text,
+            "xml" | "rss" | "atom" => Self::Xml,
+            _ => Self::Html,
+        }
+    }
+}
+
+impl FromStr for FileHandler {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "html" => Ok(Self::Html),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "text" | "txt" => Ok(Self::Text),
+            "xml" => Ok(Self::Xml),
+            _ => bail!("Unknown file handler \"{s}\". Expected html, markdown, text, or xml"),
+        }
+    }
+}
+
+/// Parses a `file_handlers` entry, such as `log=text`, into its extension and handler
+pub fn parse_file_handler(entry: &str) -> anyhow::Result<(String, FileHandler)> {
+    let Some((extension, handler)) = entry.split_once('=') else {
+        bail!("Expected an extension and a handler, such as \"log=text\", but found \"{entry}\"");
+    };
+    let extension = extension
+        .trim()
+        .trim_start_matches('.')
+        .to_ascii_lowercase();
+    if extension.is_empty() {
+        bail!("Expected an extension and a handler, such as \"log=text\", but found \"{entry}\"");
+    }
+    Ok((extension, handler.parse()?))
+}
+
+/// Renders a plain text file as an HTML page, using its first line as the title
+pub fn text_to_html(text: &str) -> String {
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+    let mut html = String::from("<html><head>");
+    if let Some(title) = text.lines().map(str::trim).find(|line| !line.is_empty()) {
+        html.push_str("<title>");
+        html.push_str(&html_escape::encode_text(title));
+        html.push_str("</title>");
+    }
+    html.push_str("</head><body>");
+
+    let mut paragraph = vec![];
+    for line in text.lines().chain(std::iter::once("")) {
+        if !line.trim().is_empty() {
+            paragraph.push(line.trim());
+            continue;
+        }
+        if !paragraph.is_empty() {
+            html.push_str("<p>");
+            html.push_str(&html_escape::encode_text(&paragraph.join(" ")));
+            html.push_str("</p>");
+            paragraph.clear();
+        }
+    }
+
+    html.push_str("</body></html>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_file_handlers() {
+        assert_eq!(
+            parse_file_handler(".LOG=text").unwrap(),
+            ("log".to_string(), FileHandler::Text)
+        );
+        assert_eq!(
+            parse_file_handler("mdx = markdown").unwrap(),
+            ("mdx".to_string(), FileHandler::Markdown)
+        );
+        assert!(parse_file_handler("log").is_err());
+        assert!(parse_file_handler("=text").is_err());
+        assert!(parse_file_handler("log=pdf").is_err());
+    }
+
+    #[test]
+    fn rendering_text() {
+        assert_eq!(
+            text_to_html("\n  Release notes\n\nFixed <this>\nand that.\n\n\n Added more.\n"),
+            "<html><head><title>Release notes</title></head><body>\
+            <p>Release notes</p><p>Fixed &lt;this&gt; and that.</p><p>Added more.</p>\
+            </body></html>"
+        );
+        assert_eq!(text_to_html(""), "<html><head></head><body></body></html>");
+    }
+}
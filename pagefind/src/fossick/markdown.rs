@@ -0,0 +1,331 @@
+//! Renders Markdown source files into HTML for the DOM parser,
+//! mapping their front matter onto Pagefind's metadata, filters, and sort keys.
+
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{Context, Result};
+use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+use serde_json::Value;
+
+use super::parser::{normalize_filter_value, DomParserResult};
+use crate::SearchOptions;
+
+/// The Pagefind data found in the front matter of a Markdown file.
+///
+/// Top-level strings, numbers, and dates become metadata, and top-level lists become filters.
+/// The `meta`, `filters`, and `sort` keys can hold a map of values to set each one explicitly.
+#[derive(Debug, Default, PartialEq)]
+pub struct FrontMatter {
+    pub meta: BTreeMap<String, String>,
+    pub filters: BTreeMap<String, Vec<String>>,
+    pub sort: BTreeMap<String, String>,
+    /// An explicit URL for the page, from the `url` or `permalink` keys
+    pub url: Option<String>,
+    /// The language of the page, from the `lang` or `language` keys
+    pub language: Option<String>,
+}
+
+impl FrontMatter {
+    fn from_value(value: Value) -> Self {
+        let mut front_matter = Self::default();
+        let Value::Object(fields) = value else {
+            return front_matter;
+        };
+
+        for (key, value) in fields {
+            match (key.as_str(), value) {
+                ("url" | "permalink", value) => {
+                    front_matter.url = front_matter.url.or_else(|| scalar_string(&value));
+                }
+                ("lang" | "language", value) => {
+                    front_matter.language = front_matter.language.or_else(|| scalar_string(&value));
+                }
+                ("meta", Value::Object(meta)) => {
+                    front_matter.meta.extend(
+                        meta.into_iter()
+                            .filter_map(|(key, value)| Some((key, scalar_string(&value)?))),
+                    );
+                }
+                ("sort", Value::Object(sort)) => {
+                    front_matter.sort.extend(
+                        sort.into_iter()
+                            .filter_map(|(key, value)| Some((key, scalar_string(&value)?))),
+                    );
+                }
+                ("filters", Value::Object(filters)) => {
+                    for (filter, values) in filters {
+                        front_matter.add_filter(filter, values);
+                    }
+                }
+                (_, value @ Value::Array(_)) => front_matter.add_filter(key, value),
+                (_, value) => {
+                    if let Some(value) = scalar_string(&value) {
+                        front_matter.meta.insert(key, value);
+                    }
+                }
+            }
+        }
+
+        front_matter
+    }
+
+    fn add_filter(&mut self, filter: String, values: Value) {
+        let values: Vec<String> = match values {
+            Value::Array(values) => values.iter().filter_map(scalar_string).collect(),
+            value => scalar_string(&value).into_iter().collect(),
+        };
+        if !values.is_empty() {
+            self.filters.entry(filter).or_default().extend(values);
+        }
+    }
+
+    /// Merges the front matter into the parsed page, taking precedence over anything found in the content.
+    pub fn apply(self, data: &mut DomParserResult, options: &SearchOptions) {
+        data.meta.extend(self.meta);
+        for (filter, values) in self.filters {
+            data.filters.entry(filter).or_default().extend(
+                values.iter().map(|value| {
+                    normalize_filter_value(value, options.filter_separator.as_deref())
+                }),
+            );
+        }
+        data.sort.extend(self.sort);
+    }
+}
+
+/// A Markdown file rendered into an HTML document
+pub struct MarkdownPage {
+    pub html: String,
+    pub front_matter: FrontMatter,
+}
+
+/// Renders a Markdown file into an HTML document, giving every heading an `id`
+/// so that headings are indexed as anchors. Front matter is parsed as YAML between `---`
+/// lines, or as TOML between `+++` lines.
+///
+/// Returns an error if the front matter can't be parsed, alongside the page rendered without it.
+pub fn render_markdown(source: &str) -> (MarkdownPage, Result<()>) {
+    let (front_matter, body) = split_front_matter(source);
+    let (front_matter, front_matter_result) = match front_matter.map(parse_front_matter) {
+        Some(Ok(front_matter)) => (front_matter, Ok(())),
+        Some(Err(e)) => (FrontMatter::default(), Err(e)),
+        None => (FrontMatter::default(), Ok(())),
+    };
+
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+
+    let mut events: Vec<Event> = Parser::new_ext(body, options).collect();
+    add_heading_ids(&mut events);
+
+    let mut html = match &front_matter.language {
+        Some(language) => format!(
+            "<html lang=\"{}\"><body>",
+            html_escape::encode_double_quoted_attribute(language)
+        ),
+        None => "<html><body>".to_string(),
+    };
+    html::push_html(&mut html, events.into_iter());
+    html.push_str("</body></html>");
+
+    (MarkdownPage { html, front_matter }, front_matter_result)
+}
+
+enum FrontMatterBlock<'a> {
+    Yaml(&'a str),
+    Toml(&'a str),
+}
+
+/// Splits a leading front matter block from the Markdown body
+fn split_front_matter(source: &str) -> (Option<FrontMatterBlock<'_>>, &str) {
+    let source = source.strip_prefix('\u{FEFF}').unwrap_or(source);
+    let Some(fence) = ["---", "+++"]
+        .into_iter()
+        .find(|fence| source.lines().next().map(str::trim_end) == Some(fence))
+    else {
+        return (None, source);
+    };
+
+    let block_start = source.find('\n').map(|i| i + 1).unwrap_or(source.len());
+    let mut offset = block_start;
+    for line in source[block_start..].split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == fence || (fence == "---" && trimmed == "...") {
+            let block = &source[block_start..offset];
+            let body = &source[offset + line.len()..];
+            let block = match fence {
+                "---" => FrontMatterBlock::Yaml(block),
+                _ => FrontMatterBlock::Toml(block),
+            };
+            return (Some(block), body);
+        }
+        offset += line.len();
+    }
+
+    // An unclosed fence isn't front matter
+    (None, source)
+}
+
+fn parse_front_matter(block: FrontMatterBlock) -> Result<FrontMatter> {
+    let value: Value = match block {
+        FrontMatterBlock::Yaml(yaml) if yaml.trim().is_empty() => Value::Null,
+        FrontMatterBlock::Yaml(yaml) => {
+            let yaml: serde_yaml::Value =
+                serde_yaml::from_str(yaml).context("Front matter is not valid YAML")?;
+            serde_json::to_value(yaml).context("Front matter keys should be strings")?
+        }
+        FrontMatterBlock::Toml(toml) => {
+            let toml: toml::Value =
+                toml::from_str(toml).context("Front matter is not valid TOML")?;
+            serde_json::to_value(toml)?
+        }
+    };
+    Ok(FrontMatter::from_value(value))
+}
+
+/// Returns a front matter value as a string, if it is a single value
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        // TOML dates serialize as a map holding the date string
+        Value::Object(date) if date.len() == 1 => date
+            .get("$__toml_private_datetime")
+            .and_then(Value::as_str)
+            .map(Into::into),
+        _ => None,
+    }
+}
+
+/// Gives each heading without an explicit `{#id}` an id built from its text,
+/// in the same style as most Markdown renderers, e.g. `## Getting Started` becomes `getting-started`.
+fn add_heading_ids(events: &mut [Event]) {
+    let mut used_ids: HashSet<String> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Tag::Heading { id: Some(id), .. }) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    for i in 0..events.len() {
+        if !matches!(events[i], Event::Start(Tag::Heading { id: None, .. })) {
+            continue;
+        }
+
+        let text: String = events[i + 1..]
+            .iter()
+            .take_while(|event| !matches!(event, Event::End(TagEnd::Heading(_))))
+            .filter_map(|event| match event {
+                Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+                _ => None,
+            })
+            .collect();
+
+        let slug = slugify(&text);
+        let mut heading_id = slug.clone();
+        let mut suffix = 0;
+        while heading_id.is_empty() || used_ids.contains(&heading_id) {
+            suffix += 1;
+            heading_id = match slug.is_empty() {
+                true => format!("section-{suffix}"),
+                false => format!("{slug}-{suffix}"),
+            };
+        }
+        used_ids.insert(heading_id.clone());
+
+        if let Event::Start(Tag::Heading { id, .. }) = &mut events[i] {
+            *id = Some(heading_id.into());
+        }
+    }
+}
+
+fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_front_matter() {
+        let (page, result) = render_markdown(
+            "---\ntitle: Hello World\ndate: 2024-01-02\ndraft: false\ntags: [rust, search]\n\
+            sort:\n  weight: 10\nfilters:\n  author: Jane\nurl: /hello/\nlang: en\n---\n# Heading\n",
+        );
+        assert!(result.is_ok());
+
+        let front_matter = page.front_matter;
+        assert_eq!(front_matter.meta.get("title").unwrap(), "Hello World");
+        assert_eq!(front_matter.meta.get("date").unwrap(), "2024-01-02");
+        assert_eq!(front_matter.meta.get("draft").unwrap(), "false");
+        assert_eq!(
+            front_matter.filters.get("tags").unwrap(),
+            &vec!["rust".to_string(), "search".to_string()]
+        );
+        assert_eq!(
+            front_matter.filters.get("author").unwrap(),
+            &vec!["Jane".to_string()]
+        );
+        assert_eq!(front_matter.sort.get("weight").unwrap(), "10");
+        assert_eq!(front_matter.url.as_deref(), Some("/hello/"));
+        assert_eq!(front_matter.language.as_deref(), Some("en"));
+        assert!(!front_matter.meta.contains_key("url"));
+
+        assert!(page.html.starts_with("<html lang=\"en\"><body>"));
+        assert!(page.html.contains("<h1 id=\"heading\">Heading</h1>"));
+    }
+
+    #[test]
+    fn toml_front_matter() {
+        let (page, result) = render_markdown(
+            "+++\ntitle = \"Hello\"\ndate = 2024-01-02\ncategories = [\"docs\"]\n+++\nBody text\n",
+        );
+        assert!(result.is_ok());
+        assert_eq!(page.front_matter.meta.get("title").unwrap(), "Hello");
+        assert_eq!(page.front_matter.meta.get("date").unwrap(), "2024-01-02");
+        assert_eq!(
+            page.front_matter.filters.get("categories").unwrap(),
+            &vec!["docs".to_string()]
+        );
+        assert!(page.html.contains("<p>Body text</p>"));
+    }
+
+    #[test]
+    fn invalid_front_matter() {
+        let (page, result) = render_markdown("---\ntitle: [unclosed\n---\nBody text\n");
+        assert!(result.is_err());
+        assert_eq!(page.front_matter, FrontMatter::default());
+        assert!(page.html.contains("<p>Body text</p>"));
+
+        let (page, result) = render_markdown("---\nNot front matter\n");
+        assert!(result.is_ok());
+        assert!(page.html.contains("<hr />"));
+    }
+
+    #[test]
+    fn heading_ids() {
+        let (page, _) = render_markdown(
+            "# Getting Started\n\n## Getting Started\n\n## Custom {#custom}\n\n## `code` & Things!\n\n## ???\n",
+        );
+        assert!(page.html.contains("<h1 id=\"getting-started\">"));
+        assert!(page.html.contains("<h2 id=\"getting-started-1\">"));
+        assert!(page.html.contains("<h2 id=\"custom\">"));
+        assert!(page.html.contains("<h2 id=\"code-things\">"));
+        assert!(page.html.contains("<h2 id=\"section-1\">"));
+    }
+}
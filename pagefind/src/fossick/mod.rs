@@ -21,8 +21,11 @@ use crate::fragments::{PageAnchorData, PageFragment, PageFragmentData};
 use crate::SearchOptions;
 use parser::DomParser;
 
+use self::handlers::{text_to_html, FileHandler};
+use self::markdown::render_markdown;
 use self::parser::DomParserResult;
 use self::splitting::get_discrete_words;
+use self::xml::{read_xml, XmlFile};
 
 lazy_static! {
     static ref NEWLINES: Regex = Regex::new("(\n|\r\n)+").unwrap();
@@ -31,8 +34,15 @@ lazy_static! {
     static ref PRIVATE_PAGEFIND: Regex = Regex::new("___PAGEFIND_[\\S]+\\s?").unwrap();
 }
 
+pub mod handlers;
+mod markdown;
 pub mod parser;
+pub mod records;
 mod splitting;
+mod stop_words;
+mod xml;
+
+pub(crate) use stop_words::get_stop_words;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct FossickedWord {
@@ -45,12 +55,18 @@ pub struct FossickedData {
     pub url: String,
     pub fragment: PageFragment,
     pub word_data: HashMap<String, Vec<FossickedWord>>,
+    /// How many times each form of a word appeared on the page before stemming, keyed by the stemmed word
+    pub word_forms: HashMap<String, HashMap<String, u32>>,
+    /// Words indexed from metadata fields, keyed by the field name
+    pub meta_word_data: HashMap<String, HashMap<String, Vec<FossickedWord>>>,
     pub sort: BTreeMap<String, String>,
     pub has_custom_body: bool,
     pub force_inclusion: bool,
     pub has_html_element: bool,
     pub has_old_bundle_reference: bool,
     pub language: String,
+    /// Multiplies the score of this page in search results
+    pub page_weight: f32,
 }
 
 #[derive(Debug)]
@@ -61,6 +77,10 @@ pub struct Fossicker {
     page_url: Option<String>,
     synthetic_content: Option<String>,
     data: Option<DomParserResult>,
+    /// Sort keys from outside the page, used when the page doesn't set its own
+    default_sort: BTreeMap<String, String>,
+    /// Multiplies the page weight set by the page itself
+    weight_multiplier: f32,
 }
 
 impl Fossicker {
@@ -71,6 +91,8 @@ impl Fossicker {
             page_url: None,
             synthetic_content: None,
             data: None,
+            default_sort: BTreeMap::new(),
+            weight_multiplier: 1.0,
         }
     }
 
@@ -85,6 +107,8 @@ impl Fossicker {
             page_url,
             synthetic_content: Some(contents),
             data: None,
+            default_sort: BTreeMap::new(),
+            weight_multiplier: 1.0,
         }
     }
 
@@ -95,9 +119,21 @@ impl Fossicker {
             page_url: Some(url),
             synthetic_content: None,
             data: Some(data),
+            default_sort: BTreeMap::new(),
+            weight_multiplier: 1.0,
         }
     }
 
+    pub fn with_default_sort(mut self, key: String, value: String) -> Self {
+        self.default_sort.insert(key, value);
+        self
+    }
+
+    pub fn with_weight_multiplier(mut self, weight_multiplier: f32) -> Self {
+        self.weight_multiplier = weight_multiplier;
+        self
+    }
+
     async fn read_file(&mut self, options: &SearchOptions) -> Result<(), Error> {
         let Some(file_path) = &self.file_path else {
             return Ok(());
@@ -154,6 +190,53 @@ impl Fossicker {
         Ok(())
     }
 
+    async fn read_markdown(&mut self, options: &SearchOptions) -> Result<(), Error> {
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+        let source = tokio::fs::read_to_string(file_path).await?;
+
+        let (page, front_matter_result) = render_markdown(&source);
+        if let Err(error) = front_matter_result {
+            options.logger.warn(format!(
+                "Failed to parse the front matter of {} — indexing this file without it. Error:\n{error:#}",
+                file_path.to_str().unwrap_or("[unknown file]"),
+            ));
+        }
+
+        let mut rewriter = DomParser::new(options);
+        if let Err(error) = rewriter.write(page.html.as_bytes()) {
+            options.logger.error(format!(
+                "Failed to parse file {} — skipping this file. Error:\n{error}",
+                file_path.to_str().unwrap_or("[unknown file]"),
+            ));
+            return Ok(());
+        }
+
+        let mut data = rewriter.wrap();
+        if self.page_url.is_none() {
+            self.page_url = page.front_matter.url.clone();
+        }
+        page.front_matter.apply(&mut data, options);
+        if let Some(forced_language) = &options.force_language {
+            data.language = forced_language.clone();
+        }
+
+        self.data = Some(data);
+
+        Ok(())
+    }
+
+    async fn read_text(&mut self, options: &SearchOptions) -> Result<(), Error> {
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+        let source = tokio::fs::read_to_string(file_path).await?;
+
+        self.synthetic_content = Some(text_to_html(&source));
+        self.read_synthetic(options).await
+    }
+
     async fn read_synthetic(&mut self, options: &SearchOptions) -> Result<(), Error> {
         let Some(contents) = self.synthetic_content.as_ref() else {
             return Ok(());
@@ -199,17 +282,20 @@ impl Fossicker {
     ) -> (
         String,
         HashMap<String, Vec<FossickedWord>>,
+        HashMap<String, HashMap<String, u32>>,
         Vec<(String, String, String, u32)>,
         usize,
     ) {
         let mut map: HashMap<String, Vec<FossickedWord>> = HashMap::new();
+        let mut forms: HashMap<String, HashMap<String, u32>> = HashMap::new();
         let mut anchors = Vec::new();
         // TODO: push this error handling up a level and return an Err from parse_digest
         if self.data.as_ref().is_none() {
-            return ("".into(), map, anchors, 0); // empty page result, will be dropped from search
+            return ("".into(), map, forms, anchors, 0); // empty page result, will be dropped from search
         }
         let data = self.data.as_ref().unwrap();
         let stemmer = get_stemmer(&data.language);
+        let stop_words = get_stop_words(&data.language, options);
 
         let mut content = String::with_capacity(data.digest.len());
 
@@ -220,6 +306,13 @@ impl Fossicker {
                 full_word.to_string()
             };
 
+            // Remember the words as they were written, so that completions can show them unstemmed
+            *forms
+                .entry(word.clone())
+                .or_default()
+                .entry(full_word.to_string())
+                .or_default() += 1;
+
             let entry = FossickedWord {
                 position: word_index.try_into().unwrap(),
                 weight: word_weight,
@@ -351,24 +444,7 @@ impl Fossicker {
             if should_segment {
                 content.push('\u{200B}');
             }
-            let mut normalized_word = String::with_capacity(base_word.len());
-            let mut possibly_compound = false;
-
-            for mut c in base_word.chars() {
-                let is_alpha = c.is_alphanumeric();
-                if !is_alpha {
-                    possibly_compound = true;
-                }
-                if is_alpha || options.include_characters.contains(&c) {
-                    c.make_ascii_lowercase();
-                    if c.is_uppercase() {
-                        // Non-ascii uppercase can lower to multiple chars
-                        normalized_word.extend(c.to_lowercase());
-                    } else {
-                        normalized_word.push(c);
-                    }
-                }
-            }
+            let (normalized_word, possibly_compound) = normalize_word(&base_word, options);
 
             let word_weight = weight_stack.last().unwrap_or(&1);
             if !normalized_word.is_empty() {
@@ -399,8 +475,11 @@ impl Fossicker {
                                 .max(1)
                             };
 
-                            // Only index two+ character words
-                            for part_word in part_words.into_iter().filter(|w| w.len() > 1) {
+                            // Only index two+ character words. Stop words are never searched
+                            // outside of phrases, which only match the full word.
+                            for part_word in part_words.into_iter().filter(|w| {
+                                w.len() > 1 && !stop_words.iter().any(|stop_word| stop_word == w)
+                            }) {
                                 store_word(part_word, total_word_index, per_weight);
                             }
                         }
@@ -438,7 +517,7 @@ impl Fossicker {
         if content.ends_with(' ') {
             content.pop();
         }
-        (content, map, anchors, max_word_index + 1)
+        (content, map, forms, anchors, max_word_index + 1)
     }
 
     /// Removes private Pagefind sentinel values from content that would otherwise leak.
@@ -464,27 +543,141 @@ impl Fossicker {
         }
     }
 
+    /// Indexes the words within any metadata fields configured in `index_meta_fields`.
+    /// Each field gets its own word map, separate from the words of the page content.
+    fn parse_meta_fields(
+        &self,
+        options: &SearchOptions,
+    ) -> HashMap<String, HashMap<String, Vec<FossickedWord>>> {
+        let mut fields = HashMap::new();
+        let Some(data) = self.data.as_ref() else {
+            return fields;
+        };
+        let stemmer = get_stemmer(&data.language);
+
+        for field in &options.index_meta_fields {
+            let Some(value) = data.meta.get(field) else {
+                continue;
+            };
+
+            let mut map: HashMap<String, Vec<FossickedWord>> = HashMap::new();
+            for (word_index, word) in value.split_whitespace().enumerate() {
+                let (normalized_word, _) = normalize_word(word, options);
+                if normalized_word.is_empty() {
+                    continue;
+                }
+                let word = if let Some(stemmer) = &stemmer {
+                    stemmer.stem(&normalized_word).into_owned()
+                } else {
+                    normalized_word
+                };
+
+                map.entry(word).or_default().push(FossickedWord {
+                    position: word_index.try_into().unwrap(),
+                    weight: 24,
+                });
+            }
+
+            if !map.is_empty() {
+                fields.insert(field.clone(), map);
+            }
+        }
+
+        fields
+    }
+
     async fn fossick_html(&mut self, options: &SearchOptions) {
         if self.synthetic_content.is_some() {
             while self.read_synthetic(options).await.is_err() {
                 sleep(Duration::from_millis(1)).await;
             }
-        } else {
-            while self.read_file(options).await.is_err() {
-                sleep(Duration::from_millis(1)).await;
+            return;
+        }
+
+        let handler = self
+            .file_path
+            .as_deref()
+            .map(|file_path| FileHandler::for_path(file_path, options))
+            .unwrap_or(FileHandler::Html);
+        match handler {
+            FileHandler::Markdown => {
+                while self.read_markdown(options).await.is_err() {
+                    sleep(Duration::from_millis(1)).await;
+                }
+            }
+            FileHandler::Text => {
+                while self.read_text(options).await.is_err() {
+                    sleep(Duration::from_millis(1)).await;
+                }
+            }
+            // XML files are read into pages by `into_pages`
+            FileHandler::Html | FileHandler::Xml => {
+                while self.read_file(options).await.is_err() {
+                    sleep(Duration::from_millis(1)).await;
+                }
             }
         }
     }
 
+    /// Reads a file that holds many pages, such as a feed, into a Fossicker for each page.
+    /// Other files are returned as they are.
+    pub async fn into_pages(self, options: &SearchOptions) -> Vec<Fossicker> {
+        let is_xml = self
+            .file_path
+            .as_deref()
+            .is_some_and(|file_path| FileHandler::for_path(file_path, options) == FileHandler::Xml);
+        if !is_xml || self.synthetic_content.is_some() || self.data.is_some() {
+            return vec![self];
+        }
+
+        let file_path = self.file_path.clone().unwrap();
+        let source = loop {
+            match tokio::fs::read(&file_path).await {
+                Ok(source) => break String::from_utf8_lossy(&source).into_owned(),
+                Err(_) => sleep(Duration::from_millis(1)).await,
+            }
+        };
+
+        match read_xml(&source) {
+            XmlFile::Feed {
+                entries,
+                without_link,
+            } => {
+                if without_link > 0 {
+                    options.logger.warn(format!(
+                        "{without_link} feed entr{} in {} did not have a link, and will not be indexed.",
+                        if without_link == 1 { "y" } else { "ies" },
+                        file_path.to_str().unwrap_or("[unknown file]"),
+                    ));
+                }
+                entries
+                    .into_iter()
+                    .map(|(url, html)| Fossicker::new_synthetic(None, Some(url), html))
+                    .collect()
+            }
+            XmlFile::Sitemap => vec![],
+            XmlFile::Document(html) => vec![Fossicker {
+                synthetic_content: Some(html),
+                ..self
+            }],
+        }
+    }
+
     pub async fn fossick(mut self, options: &SearchOptions) -> Result<FossickedData> {
         if (self.file_path.is_some() || self.synthetic_content.is_some()) && self.data.is_none() {
             self.fossick_html(options).await;
         };
 
-        let (content, word_data, anchors, word_count) = self.parse_digest(options);
+        let (content, word_data, word_forms, anchors, word_count) = self.parse_digest(options);
         self.tidy_meta_and_filters();
+        let meta_word_data = self.parse_meta_fields(options);
+
+        let mut data = self.data.unwrap();
+        for (key, value) in self.default_sort {
+            data.sort.entry(key).or_insert(value);
+        }
+        data.page_weight *= self.weight_multiplier;
 
-        let data = self.data.unwrap();
         let url = if let Some(url) = &self.page_url {
             url.clone()
         } else if let Some(path) = &self.file_path {
@@ -507,6 +700,7 @@ impl Fossicker {
             has_html_element: data.has_html_element,
             has_old_bundle_reference: data.has_old_bundle_reference,
             language: data.language,
+            page_weight: data.page_weight,
             fragment: PageFragment {
                 page_number: 0, // This page number is updated later once determined
                 data: PageFragmentData {
@@ -527,6 +721,8 @@ impl Fossicker {
                 },
             },
             word_data,
+            word_forms,
+            meta_word_data,
             sort: data.sort,
         })
     }
@@ -558,10 +754,25 @@ fn build_url(page_url: &Path, relative_to: Option<&Path>, options: &SearchOption
         return "/unknown/".to_string();
     };
 
+    let mut url = url.to_slash_lossy().to_string();
+    if FileHandler::for_path(page_url, options) == FileHandler::Markdown {
+        // Markdown files are indexed at the URL of the page they build into,
+        // e.g. docs/intro.md as /docs/intro/ and docs/index.md as /docs/
+        let source = url
+            .rsplit_once('.')
+            .map(|(source, _)| source)
+            .unwrap_or(&url);
+        url = if source == "index" || source.ends_with("/index") {
+            format!("{source}.html")
+        } else {
+            format!("{source}/index.html")
+        };
+    }
+
     let final_url: String = if !options.keep_index_url {
-        strip_index_html(&url.to_slash_lossy()).to_string()
+        strip_index_html(&url).to_string()
     } else {
-        url.to_slash_lossy().to_owned().to_string()
+        url
     };
 
     format!("/{}", final_url)
@@ -576,8 +787,48 @@ fn normalize_content(content: &str) -> String {
     content.to_string()
 }
 
+/// Lowercases a word and strips any characters that aren't indexed.
+/// Also returns whether the word contained any non-alphanumeric characters,
+/// in which case it may be a compound word worth splitting.
+fn normalize_word(word: &str, options: &SearchOptions) -> (String, bool) {
+    let mut normalized_word = String::with_capacity(word.len());
+    let mut possibly_compound = false;
+
+    for mut c in word.chars() {
+        let is_alpha = c.is_alphanumeric();
+        if !is_alpha {
+            possibly_compound = true;
+        }
+        if is_alpha || options.include_characters.contains(&c) {
+            c.make_ascii_lowercase();
+            if c.is_uppercase() {
+                // Non-ascii uppercase can lower to multiple chars
+                normalized_word.extend(c.to_lowercase());
+            } else {
+                normalized_word.push(c);
+            }
+        }
+    }
+
+    (normalized_word, possibly_compound)
+}
+
+/// Normalizes and stems text that didn't come from a page's content,
+/// matching how the words of a page in the given language are indexed
+pub(crate) fn stem_words(text: &str, language: &str, options: &SearchOptions) -> Vec<String> {
+    let stemmer = get_stemmer(language);
+    text.split_whitespace()
+        .map(|word| normalize_word(word, options).0)
+        .filter(|word| !word.is_empty())
+        .map(|word| match &stemmer {
+            Some(stemmer) => stemmer.stem(&word).into_owned(),
+            None => word,
+        })
+        .collect()
+}
+
 // TODO: These language codes are duplicated with pagefind_web's Cargo.toml
-fn get_stemmer(lang: &str) -> Option<Stemmer> {
+pub(crate) fn get_stemmer(lang: &str) -> Option<Stemmer> {
     match lang.split('-').next().unwrap() {
         "ar" => Some(Stemmer::create(Algorithm::Arabic)),
         "hy" => Some(Stemmer::create(Algorithm::Armenian)),
@@ -640,6 +891,8 @@ mod tests {
             page_url: Some("/test/".into()),
             synthetic_content: Some(s),
             data: None,
+            default_sort: BTreeMap::new(),
+            weight_multiplier: 1.0,
         };
 
         _ = f.read_synthetic(&test_opts()).await;
@@ -647,12 +900,39 @@ mod tests {
         f
     }
 
+    #[tokio::test]
+    async fn default_sorts_and_weight_multipliers() {
+        let f = test_fossick(
+            [
+                "<html><body>",
+                "<p data-pagefind-sort=\"date\">2020-01-01</p>",
+                "</body></html>",
+            ]
+            .concat(),
+        )
+        .await
+        .with_default_sort("date".into(), "2024-01-01".into())
+        .with_default_sort("updated".into(), "2024-01-01".into())
+        .with_weight_multiplier(2.0);
+
+        let data = f.fossick(&test_opts()).await.unwrap();
+
+        assert_eq!(
+            data.sort,
+            BTreeMap::from_iter([
+                ("date".to_string(), "2020-01-01".to_string()),
+                ("updated".to_string(), "2024-01-01".to_string())
+            ])
+        );
+        assert_eq!(data.page_weight, 2.0);
+    }
+
     #[tokio::test]
     async fn parse_file() {
         let mut f =
             test_fossick(["<html><body>", "<p>Hello World!</p>", "</body></html>"].concat()).await;
 
-        let (digest, words, _, _) = f.parse_digest(&test_opts());
+        let (digest, words, _, _, _) = f.parse_digest(&test_opts());
 
         assert_eq!(digest, "Hello World!".to_string());
         assert_eq!(
@@ -676,6 +956,47 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn parse_meta_field_words() {
+        let f = test_fossick(
+            [
+                "<html><body>",
+                "<h1>Hello World!</h1>",
+                "<p data-pagefind-meta='author'>Jane Doe</p>",
+                "</body></html>",
+            ]
+            .concat(),
+        )
+        .await;
+
+        let mut opts = test_opts();
+        opts.index_meta_fields = vec!["title".into(), "image".into()];
+        let fields = f.parse_meta_fields(&opts);
+
+        assert_eq!(
+            fields,
+            HashMap::from_iter([(
+                "title".to_string(),
+                HashMap::from_iter([
+                    (
+                        "hello".to_string(),
+                        vec![FossickedWord {
+                            position: 0,
+                            weight: 1 * 24
+                        }]
+                    ),
+                    (
+                        "world".to_string(),
+                        vec![FossickedWord {
+                            position: 1,
+                            weight: 1 * 24
+                        }]
+                    )
+                ])
+            )])
+        );
+    }
+
     #[tokio::test]
     async fn parse_chars() {
         let mut f = test_fossick(
@@ -690,7 +1011,7 @@ mod tests {
 
         let mut opts = test_opts();
         opts.include_characters.extend(['<', '>', '*']);
-        let (digest, words, _, _) = f.parse_digest(&opts);
+        let (digest, words, _, _, _) = f.parse_digest(&opts);
 
         assert_eq!(
             digest,
@@ -803,7 +1124,7 @@ mod tests {
         )
         .await;
 
-        let (digest, words, _, _) = f.parse_digest(&test_opts());
+        let (digest, words, _, _, _) = f.parse_digest(&test_opts());
 
         assert_eq!(digest, "The Quick Brown. Fox Jumps Over. Ryan.".to_string());
         assert_eq!(
@@ -881,7 +1202,7 @@ mod tests {
         )
         .await;
 
-        let (_, words, _, _) = f.parse_digest(&test_opts());
+        let (_, words, _, _, _) = f.parse_digest(&test_opts());
 
         assert_eq!(
             words,
@@ -939,7 +1260,7 @@ mod tests {
         )
         .await;
 
-        let (_, words, _, _) = f.parse_digest(&test_opts());
+        let (_, words, _, _, _) = f.parse_digest(&test_opts());
 
         for (_, word_positions) in words {
             for position in word_positions {
@@ -948,6 +1269,41 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn parse_compound_words_without_stop_words() {
+        let mut f = test_fossick(
+            [
+                "<html lang='en'><body>",
+                "<p>out-of-the-box</p>",
+                "</body></html>",
+            ]
+            .concat(),
+        )
+        .await;
+
+        let (_, words, _, _, _) = f.parse_digest(&test_opts());
+
+        assert_eq!(
+            words,
+            HashMap::from_iter([
+                (
+                    "outofthebox".to_string(),
+                    vec![FossickedWord {
+                        position: 0,
+                        weight: 24
+                    }]
+                ),
+                (
+                    "box".to_string(),
+                    vec![FossickedWord {
+                        position: 0,
+                        weight: 6
+                    }]
+                )
+            ])
+        );
+    }
+
     #[tokio::test]
     async fn parse_bad_weights() {
         let mut f = test_fossick(
@@ -963,7 +1319,7 @@ mod tests {
         )
         .await;
 
-        let (_, words, _, _) = f.parse_digest(&test_opts());
+        let (_, words, _, _, _) = f.parse_digest(&test_opts());
 
         assert_eq!(
             words,
@@ -1012,7 +1368,7 @@ mod tests {
         )
         .await;
 
-        let (_, words, _, _) = f.parse_digest(&test_opts());
+        let (_, words, _, _, _) = f.parse_digest(&test_opts());
 
         let mut words = words.keys().collect::<Vec<_>>();
         words.sort();
@@ -1032,7 +1388,7 @@ mod tests {
         )
         .await;
 
-        let (content, words, _, _) = f.parse_digest(&test_opts());
+        let (content, words, _, _, _) = f.parse_digest(&test_opts());
 
         let mut words = words.keys().collect::<Vec<_>>();
         words.sort();
@@ -1157,6 +1513,15 @@ mod tests {
         let p: PathBuf = cwd.join::<PathBuf>("hello/world/index.html".into());
         let root: PathBuf = cwd.join::<PathBuf>("hello".into());
         assert_eq!(&build_url(&p, Some(&root), &opts), "/world/");
+
+        let p: PathBuf = cwd.join::<PathBuf>("hello/world/index.md".into());
+        assert_eq!(&build_url(&p, None, &opts), "/");
+
+        let p: PathBuf = cwd.join::<PathBuf>("hello/world/docs/intro.md".into());
+        assert_eq!(&build_url(&p, None, &opts), "/docs/intro/");
+
+        let p: PathBuf = cwd.join::<PathBuf>("hello/world/docs/index.markdown".into());
+        assert_eq!(&build_url(&p, None, &opts), "/docs/");
     }
 
     #[cfg(target_os = "windows")]
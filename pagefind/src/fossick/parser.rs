@@ -59,6 +59,7 @@ struct DomParserData {
     default_meta: BTreeMap<String, String>,
     anchor_content: BTreeMap<String, String>,
     language: Option<String>,
+    page_weight: Option<f32>,
     has_html_element: bool,
     has_old_bundle_reference: bool,
 }
@@ -113,6 +114,7 @@ pub struct DomParserResult {
     pub has_html_element: bool,
     pub has_old_bundle_reference: bool,
     pub language: String,
+    pub page_weight: f32,
 }
 
 // Some shorthand to clean up our use of Rc<RefCell<*>> in the lol_html macros
@@ -137,6 +139,7 @@ impl<'a> DomParser<'a> {
             .map(|e| format!("{} {}", options.root_selector, e))
             .collect::<Vec<_>>()
             .join(", ");
+        let filter_separator = options.filter_separator.clone();
         let mut anchor_counter = 0;
 
         let rewriter = HtmlRewriter::new(
@@ -150,7 +153,7 @@ impl<'a> DomParser<'a> {
                         }
                         Ok(())
                     })},
-                    enclose! { (data) element!(root, move |el| {
+                    enclose! { (data, filter_separator) element!(root, move |el| {
                         let explicit_ignore_flag = el.get_attribute("data-pagefind-ignore").map(|attr| {
                             match attr.to_ascii_lowercase().as_str() {
                                 "" | "index" | "true" => NodeStatus::Ignored,
@@ -163,6 +166,15 @@ impl<'a> DomParser<'a> {
                         });
                         let treat_as_body = el.has_attribute("data-pagefind-body");
                         let weight = el.get_attribute("data-pagefind-weight").map(|attr| attr.to_string());
+                        let page_weight = el.get_attribute("data-pagefind-page-weight").and_then(|attr| {
+                            match attr.trim().parse::<f32>() {
+                                Ok(page_weight) if page_weight >= 0.0 => Some(page_weight),
+                                _ => {
+                                    options.logger.warn(format!("data-pagefind-page-weight value of \"{}\" is not valid. Expected a number of zero or above. Ignoring this page weight.", attr));
+                                    None
+                                }
+                            }
+                        });
                         let filter = el.get_attribute("data-pagefind-filter").map(|attr| parse_attr_string(attr, el));
                         let element_id = el.get_attribute("id").map(|e| ALL_SPACES.replace_all(&e, "").to_string());
                         let meta = el.get_attribute("data-pagefind-meta").map(|attr| parse_attr_string(attr, el));
@@ -256,10 +268,15 @@ impl<'a> DomParser<'a> {
 
                             drop(parent_node);
                             data.current_node = Rc::clone(&node);
+
+                            // The first page weight wins, unless it is within an excluded element
+                            if page_weight.is_some() && data.page_weight.is_none() && node.borrow().status != NodeStatus::Excluded {
+                                data.page_weight = page_weight;
+                            }
                             node
                         };
 
-                        let can_have_content = el.on_end_tag(enclose! { (data, node, tag_name) move |end| {
+                        let can_have_content = el.on_end_tag(enclose! { (data, node, tag_name, filter_separator) move |end| {
                             let mut data = data.borrow_mut();
                             let mut node = node.borrow_mut();
 
@@ -280,12 +297,11 @@ impl<'a> DomParser<'a> {
                             if let Some(filters) = &node.filter {
                                 for filter in filters {
                                     if let Some((filter, value)) = node.get_attribute_pair(filter) {
+                                        let value = normalize_filter_value(&value, filter_separator.as_deref());
                                         match data.filters.get_mut(&filter) {
-                                            Some(filter_arr) => filter_arr.push(normalize_content(&value)),
+                                            Some(filter_arr) => filter_arr.push(value),
                                             None => {
-                                                data.filters.insert(filter, vec![
-                                                    normalize_content(&value)
-                                                ]);
+                                                data.filters.insert(filter, vec![value]);
                                             }
                                         }
                                     }
@@ -432,12 +448,11 @@ impl<'a> DomParser<'a> {
                             if let Some(filters) = &node.filter {
                                 for filter in filters {
                                     if let Some((filter, value)) = node.get_attribute_pair(filter) {
+                                        let value = normalize_filter_value(&value, filter_separator.as_deref());
                                         match data.filters.get_mut(&filter) {
-                                            Some(filter_arr) => filter_arr.push(normalize_content(&value)),
+                                            Some(filter_arr) => filter_arr.push(value),
                                             None => {
-                                                data.filters.insert(filter, vec![
-                                                    normalize_content(&value)
-                                                ]);
+                                                data.filters.insert(filter, vec![value]);
                                             }
                                         }
                                     }
@@ -615,10 +630,26 @@ impl<'a> DomParser<'a> {
                 .language
                 .filter(|lang| !lang.is_empty())
                 .unwrap_or_else(|| "unknown".into()),
+            page_weight: data.page_weight.unwrap_or(1.0),
         }
     }
 }
 
+/// Normalizes a filter value, and when a filter separator is configured, tidies each
+/// level of a nested value so that "Guides>Deployment" matches "Guides > Deployment"
+pub(crate) fn normalize_filter_value(value: &str, separator: Option<&str>) -> String {
+    let value = normalize_content(value);
+    let Some(separator) = separator else {
+        return value;
+    };
+    value
+        .split(separator.trim())
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
 fn parse_attr_string(input: String, el: &Element) -> Vec<String> {
     if let Some((attrs, literal)) = input.split_once(':') {
         let mut attrs = parse_attr_string(attrs.to_owned(), el);
@@ -686,13 +717,18 @@ mod tests {
     }
 
     fn test_raw_parse(input: Vec<&'static str>) -> DomParserResult {
+        test_raw_parse_with_args(input, vec![])
+    }
+
+    fn test_raw_parse_with_args(
+        input: Vec<&'static str>,
+        args: Vec<&'static str>,
+    ) -> DomParserResult {
         use clap::CommandFactory;
+        let mut all_args = vec!["pagefind", "--source", "not_important"];
+        all_args.extend(args);
         let config_args = vec![twelf::Layer::Clap(
-            crate::PagefindInboundConfig::command().get_matches_from(vec![
-                "pagefind",
-                "--source",
-                "not_important",
-            ]),
+            crate::PagefindInboundConfig::command().get_matches_from(all_args),
         )];
         let config =
             SearchOptions::load(crate::PagefindInboundConfig::with_layers(&config_args).unwrap())
@@ -710,6 +746,29 @@ mod tests {
         test_raw_parse(input)
     }
 
+    #[test]
+    fn nested_filter_values() {
+        let data = test_raw_parse_with_args(
+            vec![
+                "<html><body>",
+                "<p data-pagefind-filter='category'>Guides > Deployment >AWS</p>",
+                "<p data-pagefind-filter='category:Guides>Hosting'></p>",
+                "<p data-pagefind-filter='tag'>Tips</p>",
+                "</body></html>",
+            ],
+            vec!["--filter-separator", " > "],
+        );
+
+        assert_eq!(
+            data.filters.get("category"),
+            Some(&vec![
+                "Guides > Deployment > AWS".to_string(),
+                "Guides > Hosting".to_string()
+            ])
+        );
+        assert_eq!(data.filters.get("tag"), Some(&vec!["Tips".to_string()]));
+    }
+
     #[test]
     fn words_weights() {
         let data = test_parse(vec![
@@ -723,6 +782,24 @@ mod tests {
         )
     }
 
+    #[test]
+    fn page_weights() {
+        let data = test_parse(vec!["<p>Weight one</p>"]);
+        assert_eq!(data.page_weight, 1.0);
+
+        let data = test_raw_parse(vec![
+            "<html><head><meta data-pagefind-page-weight='2.5'></head>",
+            "<body><p data-pagefind-page-weight='4'>Weight two</p></body></html>",
+        ]);
+        assert_eq!(data.page_weight, 2.5);
+
+        let data = test_parse(vec![
+            "<div data-pagefind-ignore='all' data-pagefind-page-weight='3'></div>",
+            "<p data-pagefind-page-weight='heavy'>Weight one</p>",
+        ]);
+        assert_eq!(data.page_weight, 1.0);
+    }
+
     #[test]
     fn words_ids() {
         let data = test_parse(vec![
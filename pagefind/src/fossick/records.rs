@@ -0,0 +1,171 @@
+//! Custom records that aren't backed by an HTML file,
+//! read in bulk from JSON or JSON Lines files.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use super::parser::{normalize_filter_value, DomParserResult};
+use super::Fossicker;
+use crate::SearchOptions;
+
+/// A custom record, in the same shape as `PagefindIndex::add_custom_record`
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct CustomRecord {
+    /// The output URL of this record. Pagefind will not alter this.
+    pub url: String,
+    /// The raw content of this record.
+    pub content: String,
+    /// What language is this record written in. Expects an ISO 639-1 code.
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub meta: BTreeMap<String, String>,
+    #[serde(default)]
+    pub filters: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub sort: BTreeMap<String, String>,
+}
+
+impl CustomRecord {
+    pub fn into_fossicker(self, options: &SearchOptions) -> Fossicker {
+        let filters = self
+            .filters
+            .into_iter()
+            .map(|(filter, values)| {
+                let values = values
+                    .iter()
+                    .map(|value| normalize_filter_value(value, options.filter_separator.as_deref()))
+                    .collect();
+                (filter, values)
+            })
+            .collect();
+        let data = DomParserResult {
+            digest: self.content,
+            filters,
+            sort: self.sort,
+            meta: self.meta,
+            anchor_content: BTreeMap::new(),
+            has_custom_body: false,
+            force_inclusion: true,
+            has_html_element: true,
+            has_old_bundle_reference: false,
+            language: options
+                .force_language
+                .clone()
+                .or(self.language)
+                .unwrap_or_else(|| "unknown".into()),
+            page_weight: 1.0,
+        };
+        Fossicker::new_with_data(self.url, data)
+    }
+}
+
+/// Parses the custom records in a file. Files ending in `.jsonl` or `.ndjson` are read as JSON Lines,
+/// with one record per line. Other files are read as JSON, holding either one record or a list of records.
+pub fn parse_records(path: &Path, contents: &str) -> Result<Vec<CustomRecord>> {
+    let is_json_lines = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("jsonl" | "ndjson")
+    );
+
+    if is_json_lines {
+        return contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Failed to parse the record on line {}", i + 1))
+            })
+            .collect();
+    }
+
+    let value: serde_json::Value =
+        serde_json::from_str(contents).context("Failed to parse JSON")?;
+    let records = match value {
+        serde_json::Value::Array(records) => records
+            .into_iter()
+            .enumerate()
+            .map(|(i, record)| {
+                serde_json::from_value(record)
+                    .with_context(|| format!("Failed to parse the record at index {i}"))
+            })
+            .collect::<Result<_>>()?,
+        value @ serde_json::Value::Object(_) => {
+            vec![serde_json::from_value(value).context("Failed to parse the record")?]
+        }
+        _ => bail!("Expected a record, or a list of records"),
+    };
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_json_records() {
+        let records = parse_records(
+            Path::new("records.json"),
+            r#"[
+                {"url": "/a/", "content": "Apple", "language": "en", "meta": {"title": "A"}},
+                {"url": "/b/", "content": "Banana", "filters": {"fruit": ["yellow"]}, "sort": {"weight": "2"}}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].language.as_deref(), Some("en"));
+        assert_eq!(records[0].meta.get("title").unwrap(), "A");
+        assert_eq!(records[1].language, None);
+        assert_eq!(
+            records[1].filters.get("fruit").unwrap(),
+            &vec!["yellow".to_string()]
+        );
+        assert_eq!(records[1].sort.get("weight").unwrap(), "2");
+
+        let records = parse_records(
+            Path::new("record.json"),
+            r#"{"url": "/a/", "content": "Apple"}"#,
+        )
+        .unwrap();
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn parsing_json_lines_records() {
+        let records = parse_records(
+            Path::new("records.jsonl"),
+            "{\"url\": \"/a/\", \"content\": \"Apple\"}\n\n{\"url\": \"/b/\", \"content\": \"Banana\"}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            records.iter().map(|r| r.url.as_str()).collect::<Vec<_>>(),
+            vec!["/a/", "/b/"]
+        );
+    }
+
+    #[test]
+    fn invalid_records() {
+        let error = parse_records(
+            Path::new("records.ndjson"),
+            "{\"url\": \"/a/\", \"content\": \"Apple\"}\n{\"url\": \"/b/\"}\n",
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Failed to parse the record on line 2");
+
+        let error = parse_records(
+            Path::new("records.json"),
+            r#"[{"url": "/a/", "content": "Apple"}, {"content": "Banana"}]"#,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Failed to parse the record at index 1");
+
+        assert!(parse_records(Path::new("records.json"), "\"/a/\"").is_err());
+    }
+}
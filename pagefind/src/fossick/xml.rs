@@ -0,0 +1,334 @@
+//! Reads XML files, turning each entry of an RSS or Atom feed into its own page.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::utils::{xml_element_text, xml_text};
+
+lazy_static! {
+    /// The name of the root element, after any XML declaration, comments, or doctype
+    static ref ROOT: Regex =
+        Regex::new(r"(?s)^\s*(?:(?:<\?.*?\?>|<!--.*?-->|<!DOCTYPE[^>]*>)\s*)*<([\w:.-]+)").unwrap();
+    static ref RSS_ITEM: Regex = Regex::new(r"(?s)<item\b[^>]*>(.*?)</item>").unwrap();
+    static ref ATOM_ENTRY: Regex = Regex::new(r"(?s)<entry\b[^>]*>(.*?)</entry>").unwrap();
+    static ref RSS_LANGUAGE: Regex = element("language");
+    static ref ATOM_FEED: Regex = Regex::new(r"<feed\b([^>]*)>").unwrap();
+    static ref TITLE: Regex = element("title");
+    static ref LINK: Regex = element("link");
+    static ref GUID: Regex = element("guid");
+    static ref DESCRIPTION: Regex = element("description");
+    static ref CONTENT_ENCODED: Regex = element("content:encoded");
+    static ref CONTENT: Regex = element("content");
+    static ref SUMMARY: Regex = element("summary");
+    static ref PUB_DATE: Regex = element("pubDate");
+    static ref PUBLISHED: Regex = element("published");
+    static ref UPDATED: Regex = element("updated");
+    static ref CATEGORY: Regex = element("category");
+    static ref ATOM_LINK: Regex = Regex::new(r"<link\b([^>]*?)/?>").unwrap();
+    static ref ATOM_CATEGORY: Regex = Regex::new(r"<category\b([^>]*?)/?>").unwrap();
+    static ref ATTRIBUTE: Regex =
+        Regex::new(r#"([\w:.-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    static ref CDATA: Regex = Regex::new(r"(?s)<!\[CDATA\[(.*?)\]\]>").unwrap();
+    static ref COMMENT: Regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+    static ref TAG: Regex = Regex::new(r"(?s)<[^>]*>").unwrap();
+    static ref WHITESPACE: Regex = Regex::new(r"\s+").unwrap();
+}
+
+fn element(name: &str) -> Regex {
+    Regex::new(&format!(
+        r"(?s)<{name}(?:\s[^>]*)?>(.*?)</{name}>",
+        name = regex::escape(name)
+    ))
+    .unwrap()
+}
+
+#[derive(Debug, PartialEq)]
+pub enum XmlFile {
+    Feed {
+        /// Each entry of the feed, as a URL and an HTML page for the entry
+        entries: Vec<(String, String)>,
+        /// How many entries were skipped for not having a link to use as their URL
+        without_link: usize,
+    },
+    /// Sitemaps list pages rather than holding content, so aren't indexed
+    Sitemap,
+    /// Any other XML file, as an HTML page holding the text of the file
+    Document(String),
+}
+
+/// Reads an XML file by its root element, so that RSS and Atom feeds are split into their entries.
+pub fn read_xml(xml: &str) -> XmlFile {
+    let root = ROOT
+        .captures(xml.trim_start_matches('\u{FEFF}'))
+        .and_then(|root| root.get(1))
+        .map(|root| root.as_str());
+
+    match root {
+        Some("urlset" | "sitemapindex") => XmlFile::Sitemap,
+        Some("rss" | "rdf:RDF") => read_rss(xml),
+        Some("feed") => read_atom(xml),
+        _ => XmlFile::Document(document_to_html(xml)),
+    }
+}
+
+fn read_rss(xml: &str) -> XmlFile {
+    let language = xml_element_text(&RSS_LANGUAGE, xml);
+    let mut without_link = 0;
+    let entries = RSS_ITEM
+        .captures_iter(xml)
+        .filter_map(|item| {
+            let item = &item[1];
+            let Some(url) = xml_element_text(&LINK, item).or_else(|| xml_element_text(&GUID, item))
+            else {
+                without_link += 1;
+                return None;
+            };
+            let entry = FeedEntry {
+                title: xml_element_text(&TITLE, item),
+                content: xml_element_text(&CONTENT_ENCODED, item)
+                    .or_else(|| xml_element_text(&DESCRIPTION, item)),
+                date: xml_element_text(&PUB_DATE, item),
+                categories: CATEGORY
+                    .captures_iter(item)
+                    .filter_map(|category| xml_text(&category[1]))
+                    .collect(),
+            };
+            Some((url, entry.to_html(language.as_deref())))
+        })
+        .collect();
+
+    XmlFile::Feed {
+        entries,
+        without_link,
+    }
+}
+
+fn read_atom(xml: &str) -> XmlFile {
+    let language = ATOM_FEED
+        .captures(xml)
+        .and_then(|feed| attribute(&feed[1], "xml:lang"));
+    let mut without_link = 0;
+    let entries = ATOM_ENTRY
+        .captures_iter(xml)
+        .filter_map(|entry| {
+            let entry = &entry[1];
+            let url = ATOM_LINK
+                .captures_iter(entry)
+                .map(|link| link[1].to_string())
+                .find(|attrs| {
+                    matches!(attribute(attrs, "rel").as_deref(), None | Some("alternate"))
+                })
+                .and_then(|attrs| attribute(&attrs, "href"));
+            let Some(url) = url else {
+                without_link += 1;
+                return None;
+            };
+            let entry = FeedEntry {
+                title: xml_element_text(&TITLE, entry),
+                content: xml_element_text(&CONTENT, entry)
+                    .or_else(|| xml_element_text(&SUMMARY, entry)),
+                date: xml_element_text(&PUBLISHED, entry)
+                    .or_else(|| xml_element_text(&UPDATED, entry)),
+                categories: ATOM_CATEGORY
+                    .captures_iter(entry)
+                    .filter_map(|category| attribute(&category[1], "term"))
+                    .collect(),
+            };
+            Some((url, entry.to_html(language.as_deref())))
+        })
+        .collect();
+
+    XmlFile::Feed {
+        entries,
+        without_link,
+    }
+}
+
+struct FeedEntry {
+    title: Option<String>,
+    /// The HTML content of the entry
+    content: Option<String>,
+    date: Option<String>,
+    categories: Vec<String>,
+}
+
+impl FeedEntry {
+    fn to_html(&self, language: Option<&str>) -> String {
+        let mut html = match language {
+            Some(language) => format!(
+                "<html lang=\"{}\"><head>",
+                html_escape::encode_double_quoted_attribute(language)
+            ),
+            None => "<html><head>".to_string(),
+        };
+        if let Some(date) = &self.date {
+            html.push_str(&format!(
+                "<meta data-pagefind-meta=\"date[content]\" content=\"{}\">",
+                html_escape::encode_double_quoted_attribute(date)
+            ));
+        }
+        for category in &self.categories {
+            html.push_str(&format!(
+                "<meta data-pagefind-filter=\"category[content]\" content=\"{}\">",
+                html_escape::encode_double_quoted_attribute(category)
+            ));
+        }
+        html.push_str("</head><body>");
+        if let Some(title) = &self.title {
+            // Feed titles are text, though some feeds escape HTML within them
+            let title = TAG.replace_all(title, "");
+            html.push_str(&format!("<h1>{}</h1>", html_escape::encode_text(&title)));
+        }
+        if let Some(content) = &self.content {
+            html.push_str(content);
+        }
+        html.push_str("</body></html>");
+        html
+    }
+}
+
+/// Returns the decoded value of an attribute, given the attributes of an element
+fn attribute(attrs: &str, name: &str) -> Option<String> {
+    let captures = ATTRIBUTE
+        .captures_iter(attrs)
+        .find(|attribute| &attribute[1] == name)?;
+    let value = captures.get(2).or_else(|| captures.get(3))?.as_str();
+    let value = html_escape::decode_html_entities(value);
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Renders the text of an XML document as an HTML page
+fn document_to_html(xml: &str) -> String {
+    let xml = COMMENT.replace_all(xml, " ");
+    let xml = CDATA.replace_all(&xml, |cdata: &regex::Captures| {
+        html_escape::encode_text(&cdata[1]).into_owned()
+    });
+    let text = TAG.replace_all(&xml, " ");
+    let text = html_escape::decode_html_entities(&text);
+    let text = WHITESPACE.replace_all(&text, " ");
+
+    format!(
+        "<html><body><p>{}</p></body></html>",
+        html_escape::encode_text(text.trim())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_rss_feeds() {
+        let XmlFile::Feed {
+            entries,
+            without_link,
+        } = read_xml(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+                <channel>
+                    <title>Blog</title>
+                    <link>https://example.com/</link>
+                    <language>en</language>
+                    <item>
+                        <title>First &amp; best</title>
+                        <link>https://example.com/first/</link>
+                        <description>Summary</description>
+                        <content:encoded><![CDATA[<p>Full <b>content</b></p>]]></content:encoded>
+                        <pubDate>Tue, 02 Jan 2024 10:00:00 GMT</pubDate>
+                        <category>News</category>
+                        <category>Rust</category>
+                    </item>
+                    <item>
+                        <guid isPermaLink="true">https://example.com/second/</guid>
+                        <description>&lt;p&gt;Escaped content&lt;/p&gt;</description>
+                    </item>
+                    <item><title>No link</title></item>
+                </channel>
+            </rss>"#,
+        )
+        else {
+            panic!("Expected a feed");
+        };
+
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "https://example.com/first/".to_string(),
+                    "<html lang=\"en\"><head>\
+                    <meta data-pagefind-meta=\"date[content]\" content=\"Tue, 02 Jan 2024 10:00:00 GMT\">\
+                    <meta data-pagefind-filter=\"category[content]\" content=\"News\">\
+                    <meta data-pagefind-filter=\"category[content]\" content=\"Rust\">\
+                    </head><body><h1>First &amp; best</h1><p>Full <b>content</b></p></body></html>"
+                        .to_string()
+                ),
+                (
+                    "https://example.com/second/".to_string(),
+                    "<html lang=\"en\"><head></head><body><p>Escaped content</p></body></html>"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(without_link, 1);
+    }
+
+    #[test]
+    fn reading_atom_feeds() {
+        let XmlFile::Feed {
+            entries,
+            without_link,
+        } = read_xml(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="fr">
+                <title>Blog</title>
+                <link href="https://example.com/" />
+                <entry>
+                    <title type="text">Bonjour</title>
+                    <link rel="edit" href="https://example.com/edit/1" />
+                    <link rel="alternate" type="text/html" href="https://example.com/bonjour/"/>
+                    <published>2024-01-02T10:00:00Z</published>
+                    <category term="Nouvelles" />
+                    <summary>Summary</summary>
+                    <content type="html">&lt;p&gt;Contenu&lt;/p&gt;</content>
+                </entry>
+            </feed>"#,
+        )
+        else {
+            panic!("Expected a feed");
+        };
+
+        assert_eq!(
+            entries,
+            vec![(
+                "https://example.com/bonjour/".to_string(),
+                "<html lang=\"fr\"><head>\
+                <meta data-pagefind-meta=\"date[content]\" content=\"2024-01-02T10:00:00Z\">\
+                <meta data-pagefind-filter=\"category[content]\" content=\"Nouvelles\">\
+                </head><body><h1>Bonjour</h1><p>Contenu</p></body></html>"
+                    .to_string()
+            )]
+        );
+        assert_eq!(without_link, 0);
+    }
+
+    #[test]
+    fn reading_other_xml() {
+        assert_eq!(
+            read_xml("<urlset><url><loc>https://example.com/</loc></url></urlset>"),
+            XmlFile::Sitemap
+        );
+        assert_eq!(
+            read_xml(
+                "<?xml version=\"1.0\"?><!-- comment --><book>\n<title>Tom &amp; Jerry</title>\
+                <note><![CDATA[a < b]]></note></book>"
+            ),
+            XmlFile::Document(
+                "<html><body><p>Tom &amp; Jerry a &lt; b</p></body></html>".to_string()
+            )
+        );
+        assert_eq!(
+            read_xml("<catalog><item><link>https://example.com/</link></item></catalog>"),
+            XmlFile::Document("<html><body><p>https://example.com/</p></body></html>".to_string())
+        );
+    }
+}
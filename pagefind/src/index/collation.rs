@@ -0,0 +1,75 @@
+use std::cmp::Ordering;
+
+use icu_collator::{Collator, CollatorOptions};
+use icu_provider::DataLocale;
+
+/// Orders strings alphabetically for a language, rather than by their bytes,
+/// so that "Éclair" sorts alongside "eclair" and ahead of "Zebra".
+///
+/// Strings are compared with the Unicode Collation Algorithm, using the CLDR tailoring
+/// for the language where one exists (such as Swedish placing "ö" after "z"),
+/// and the root collation order for all other languages.
+pub struct LanguageCollator {
+    collator: Option<Collator>,
+}
+
+impl LanguageCollator {
+    pub fn new(language: &str) -> Self {
+        let locale = language.parse::<DataLocale>().unwrap_or_default();
+        let collator = Collator::try_new(&locale, CollatorOptions::new())
+            .or_else(|_| Collator::try_new(&DataLocale::default(), CollatorOptions::new()))
+            .ok();
+        Self { collator }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match &self.collator {
+            Some(collator) => collator.compare(a, b),
+            None => a.cmp(b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(values: &[&'static str], language: &str) -> Vec<&'static str> {
+        let collator = LanguageCollator::new(language);
+        let mut values = values.to_vec();
+        values.sort_by(|a, b| collator.compare(a, b));
+        values
+    }
+
+    #[test]
+    fn collating_strings() {
+        assert_eq!(
+            sorted(&["Zebra", "éclair", "Eclair", "apple", "Émile"], "en"),
+            vec!["apple", "Eclair", "éclair", "Émile", "Zebra"]
+        );
+        assert_eq!(
+            sorted(&["Öl", "Zebra", "Apple", "Åsa"], "de"),
+            vec!["Apple", "Åsa", "Öl", "Zebra"]
+        );
+        assert_eq!(
+            sorted(&["Öl", "Zebra", "Apple", "Åsa"], "sv-se"),
+            vec!["Apple", "Zebra", "Åsa", "Öl"]
+        );
+        assert_eq!(
+            sorted(&["nube", "ñu", "oso"], "es"),
+            vec!["nube", "ñu", "oso"]
+        );
+        assert_eq!(
+            sorted(&["Ωμέγα", "άλφα", "Βήτα"], "el"),
+            vec!["άλφα", "Βήτα", "Ωμέγα"]
+        );
+        assert_eq!(
+            sorted(&["Яблоко", "ёж", "Арбуз", "еда"], "ru"),
+            vec!["Арбуз", "еда", "ёж", "Яблоко"]
+        );
+        assert_eq!(
+            sorted(&["Zebra", "éclair", "apple"], "unknown"),
+            vec!["apple", "éclair", "Zebra"]
+        );
+    }
+}
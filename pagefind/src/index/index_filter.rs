@@ -9,6 +9,14 @@ pub struct FilterIndex {
     pub filter: String,
     #[n(1)]
     pub values: Vec<PackedValue>,
+    /// For filters where every value is a number or a date, the position of each value
+    /// on a number line, in the same order as `values`. Empty for all other filters.
+    #[n(2)]
+    pub orders: Vec<f64>,
+    /// For nested filters, the position of each value's parent in `values`, plus one,
+    /// with top-level values set to zero. Empty for filters without nested values.
+    #[n(3)]
+    pub parents: Vec<u32>,
 }
 
 /// A single filter value as an inverse index of all locations on the site
@@ -15,6 +15,14 @@ pub struct MetaIndex {
     pub filters: Vec<MetaFilter>,
     #[n(4)]
     pub sorts: Vec<MetaSort>,
+    #[n(5)]
+    pub meta_fields: Vec<MetaField>,
+    #[n(6)]
+    pub synonyms: Vec<MetaSynonym>,
+    #[n(7)]
+    pub stop_words: Vec<String>,
+    #[n(8)]
+    pub meta_dates: Vec<MetaDates>,
 }
 
 /// Communicates the pagefind/index/*.pf_index file we need to load
@@ -35,6 +43,8 @@ pub struct MetaPage {
     pub hash: String,
     #[n(1)]
     pub word_count: u32,
+    #[n(2)]
+    pub page_weight: f32,
 }
 
 #[derive(Encode, Debug)]
@@ -51,4 +61,40 @@ pub struct MetaSort {
     pub sort: String,
     #[n(1)]
     pub pages: Vec<usize>,
+    /// The rank of each page in `pages`, where pages with equal sort values share a rank
+    #[n(2)]
+    pub ranks: Vec<u32>,
+    /// The date of each page in `pages` as seconds since the epoch, if this is a date sort
+    #[n(3)]
+    pub dates: Vec<f64>,
+}
+
+/// The date of each page for a metadata field where every value is a date
+#[derive(Encode, PartialEq, Debug)]
+pub struct MetaDates {
+    #[n(0)]
+    pub field: String,
+    #[n(1)]
+    pub pages: Vec<usize>,
+    /// The date of each page in `pages` as seconds since the epoch
+    #[n(2)]
+    pub dates: Vec<f64>,
+}
+
+/// The word index chunks for a metadata field that is searchable on its own
+#[derive(Encode, Debug)]
+pub struct MetaField {
+    #[n(0)]
+    pub field: String,
+    #[n(1)]
+    pub index_chunks: Vec<MetaChunk>,
+}
+
+/// The stemmed terms (or space separated phrases) that a search for `term` should also match
+#[derive(Encode, PartialEq, Debug)]
+pub struct MetaSynonym {
+    #[n(0)]
+    pub term: String,
+    #[n(1)]
+    pub synonyms: Vec<String>,
 }
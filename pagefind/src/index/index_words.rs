@@ -7,6 +7,9 @@ use minicbor::Encode;
 pub struct WordIndex {
     #[n(0)]
     pub words: Vec<PackedWord>,
+    /// The metadata field these words were indexed from, empty for page content
+    #[n(1)]
+    pub field: String,
 }
 
 /// A single word as an inverse index of all locations on the site
@@ -16,6 +19,9 @@ pub struct PackedWord {
     pub word: String,
     #[n(1)]
     pub pages: Vec<PackedPage>,
+    /// The most common form of this word on the site before stemming, empty if it is the same as the word
+    #[n(2)]
+    pub form: String,
 }
 
 /// A set of locations on a given page
@@ -1,18 +1,22 @@
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
+use std::collections::BTreeMap;
 
 use crate::{
-    fossick::{FossickedData, FossickedWord},
-    index::index_metadata::MetaFilter,
-    utils::full_hash,
+    fossick::{get_stop_words, stem_words, FossickedData, FossickedWord},
+    index::index_metadata::{MetaFilter, MetaSynonym},
+    options::SynonymGroup,
+    utils::{full_hash, parse_formatted_date, parse_iso_date},
     SearchOptions,
 };
 use anyhow::{bail, Result};
+use collation::LanguageCollator;
 use index_filter::{FilterIndex, PackedValue};
-use index_metadata::{MetaChunk, MetaIndex, MetaPage};
+use index_metadata::{MetaChunk, MetaDates, MetaField, MetaIndex, MetaPage};
 use index_words::{PackedPage, PackedWord, WordIndex};
 
 use self::index_metadata::MetaSort;
 
+mod collation;
 mod index_filter;
 mod index_metadata;
 mod index_words;
@@ -32,6 +36,7 @@ struct IntermediaryPageData {
     full_hash: String,
     encoded_data: String,
     word_count: usize,
+    page_weight: f32,
     page_number: usize,
 }
 
@@ -39,6 +44,14 @@ struct IntermediaryPageData {
 enum SortType {
     String,
     Number,
+    Date,
+}
+
+/// A sort value, parsed as the type of its sort
+enum SortValue {
+    String(String),
+    Number(f32),
+    Date(f64),
 }
 
 pub async fn build_indexes(
@@ -52,6 +65,10 @@ pub async fn build_indexes(
         index_chunks: Vec::new(),
         filters: Vec::new(),
         sorts: Vec::new(),
+        meta_fields: Vec::new(),
+        synonyms: build_synonyms(&language, options),
+        stop_words: get_stop_words(&language, options),
+        meta_dates: Vec::new(),
     };
 
     /*
@@ -63,6 +80,8 @@ pub async fn build_indexes(
     */
 
     let mut word_map: HashMap<String, PackedWord> = HashMap::new();
+    let mut meta_field_word_maps: BTreeMap<String, HashMap<String, PackedWord>> = BTreeMap::new();
+    let mut word_forms: HashMap<String, HashMap<String, u32>> = HashMap::new();
     let mut filter_map: HashMap<String, HashMap<String, Vec<usize>>> = HashMap::new();
     let mut fragment_hashes: HashMap<String, IntermediaryPageData> = HashMap::new();
     let mut fragments: Vec<(usize, (String, IntermediaryPageData))> = Vec::new();
@@ -82,87 +101,86 @@ pub async fn build_indexes(
     // Determine the best sorting parser that fits all available values for each given key
     let mut sort_types: HashMap<String, SortType> = HashMap::new();
     for sort in sorts.iter() {
-        let mut sort_values = pages.iter().flat_map(|page| page.sort.get(sort));
+        let sort_values = || pages.iter().flat_map(|page| page.sort.get(sort));
         sort_types.insert(
             sort.clone(),
-            if sort_values.all(|v| parse_int_sort(v).is_some() || parse_float_sort(v).is_some()) {
+            if sort_values().all(|v| parse_int_sort(v).is_some() || parse_float_sort(v).is_some()) {
                 SortType::Number
+            } else if sort_values().all(|v| parse_date_sort(v, options).is_some()) {
+                SortType::Date
             } else {
                 SortType::String
             },
         );
     }
 
+    let collator = LanguageCollator::new(&language);
     for (sort_key, sort_type) in sort_types {
         let mut page_values: Vec<_> = pages
             .iter()
             .flat_map(|page| {
-                page.sort
-                    .get(&sort_key)
-                    .map(|v| (v, page.fragment.page_number))
+                page.sort.get(&sort_key).map(|v| {
+                    (
+                        parse_sort_value(&sort_type, v, options),
+                        page.fragment.page_number,
+                    )
+                })
             })
             .collect();
         options.logger.v_info(format!(
             "Prebuilding sort order for {sort_key}, processed as type: {sort_type:#?}"
         ));
-        match sort_type {
-            SortType::String => page_values.sort_by_key(|p| p.0),
-            SortType::Number => page_values.sort_by(|p1, p2| {
-                let p1 = parse_int_sort(p1.0)
-                    .map(|i| i as f32)
-                    .unwrap_or_else(|| parse_float_sort(p1.0).unwrap_or_default());
-                let p2 = parse_int_sort(p2.0)
-                    .map(|i| i as f32)
-                    .unwrap_or_else(|| parse_float_sort(p2.0).unwrap_or_default());
-
-                p1.total_cmp(&p2)
-            }),
+        page_values.sort_by(|p1, p2| p1.0.compare(&p2.0, &collator));
+
+        // Pages with equal values share a rank, so that other sorts can break the tie
+        let mut ranks = Vec::with_capacity(page_values.len());
+        let mut rank = 0;
+        for (i, (value, _)) in page_values.iter().enumerate() {
+            if i > 0 && page_values[i - 1].0.compare(value, &collator).is_ne() {
+                rank += 1;
+            }
+            ranks.push(rank);
         }
+
+        let dates = page_values
+            .iter()
+            .filter_map(|(value, _)| match value {
+                SortValue::Date(date) => Some(*date),
+                _ => None,
+            })
+            .collect();
+
         meta.sorts.push(MetaSort {
             sort: sort_key,
             pages: page_values.into_iter().map(|p| p.1).collect(),
+            ranks,
+            dates,
         });
     }
 
+    meta.meta_dates = build_meta_dates(
+        pages
+            .iter()
+            .map(|page| (page.fragment.page_number, &page.fragment.data.meta)),
+        options,
+    );
+
     for page in pages.into_iter() {
-        for (word, mut positions) in page.word_data {
-            // A page weight of 1 is encoded as 25. Since most words should be this weight,
-            // we want to sort them to be first in the locations array to reduce filesize
-            // when we inline weight changes
-            positions.sort_by_cached_key(|p| if p.weight == 25 { 0 } else { p.weight });
-
-            let mut current_weight = 25;
-            let mut weighted_positions = Vec::with_capacity(positions.len());
-            // Calculate our output list of positions with weights.
-            // This is a vec of page positions, with a change in weight for subsequent positions
-            // denoted by a negative integer.
-            positions
-                .into_iter()
-                .for_each(|FossickedWord { position, weight }| {
-                    if weight != current_weight {
-                        weighted_positions.extend([(weight as i32) * -1 - 1, position as i32]);
-                        current_weight = weight;
-                    } else {
-                        weighted_positions.push(position as i32)
-                    }
-                });
+        for (word, positions) in page.word_data {
+            pack_word(&mut word_map, word, positions, page.fragment.page_number);
+        }
 
-            let packed_page = PackedPage {
-                page_number: page.fragment.page_number,
-                locs: weighted_positions,
-            };
+        for (word, forms) in page.word_forms {
+            let site_forms = word_forms.entry(word).or_default();
+            for (form, count) in forms {
+                *site_forms.entry(form).or_default() += count;
+            }
+        }
 
-            match word_map.get_mut(&word) {
-                Some(packed) => packed.pages.push(packed_page),
-                None => {
-                    word_map.insert(
-                        word.clone(),
-                        PackedWord {
-                            word,
-                            pages: vec![packed_page],
-                        },
-                    );
-                }
+        for (field, words) in page.meta_word_data {
+            let field_word_map = meta_field_word_maps.entry(field).or_default();
+            for (word, positions) in words {
+                pack_word(field_word_map, word, positions, page.fragment.page_number);
             }
         }
 
@@ -188,6 +206,7 @@ pub async fn build_indexes(
         let encoded_page = IntermediaryPageData {
             full_hash: format!("{}_{}", language, full_hash(encoded_data.as_bytes())),
             word_count: page.fragment.data.word_count,
+            page_weight: page.page_weight,
             page_number: page.fragment.page_number,
             encoded_data,
         };
@@ -221,22 +240,40 @@ pub async fn build_indexes(
     fragments.sort_by_cached_key(|(_, (_, fragment))| fragment.page_number);
 
     meta.pages
-        .extend(fragments.iter().map(|(word_count, (hash, _))| MetaPage {
+        .extend(fragments.iter().map(|(word_count, (hash, frag))| MetaPage {
             hash: hash.clone(),
             word_count: *word_count as u32,
+            page_weight: frag.page_weight,
         }));
 
     // TODO: Change filter indexes to BTree to give them a stable hash.
     let mut filter_indexes = HashMap::new();
     for (filter, values) in filter_map {
+        let mut values: Vec<PackedValue> = values
+            .into_iter()
+            .map(|(value, pages)| PackedValue { value, pages })
+            .collect();
+        if let Some(separator) = &options.filter_separator {
+            add_filter_ancestors(&mut values, separator);
+        }
+        let mut orders = filter_value_orders(&values);
+        if !orders.is_empty() {
+            options.logger.v_info(format!(
+                "Ordering the values of the {filter} filter, so it can be filtered by range"
+            ));
+            let mut ordered_values: Vec<_> = orders.into_iter().zip(values).collect();
+            ordered_values.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+            (orders, values) = ordered_values.into_iter().unzip();
+        }
+        let parents = filter_value_parents(&values, options.filter_separator.as_deref());
+
         let mut filter_index: Vec<u8> = Vec::new();
         let _ = minicbor::encode::<FilterIndex, &mut Vec<u8>>(
             FilterIndex {
                 filter: filter.clone(),
-                values: values
-                    .into_iter()
-                    .map(|(value, pages)| PackedValue { value, pages })
-                    .collect(),
+                values,
+                orders,
+                parents,
             },
             filter_index.as_mut(),
         );
@@ -272,33 +309,36 @@ pub async fn build_indexes(
         );
     }
 
+    set_word_forms(&mut word_map, word_forms);
+
     // TODO: Parameterize these chunk sizes via options
     let word_count = word_map.len();
     let chunks = chunk_index(word_map, 20000);
     meta.index_chunks = chunk_meta(&chunks);
 
     let mut word_indexes: HashMap<String, Vec<u8>> = HashMap::new();
-    for (i, chunk) in chunks.into_iter().enumerate() {
-        let mut word_index: Vec<u8> = Vec::new();
-        let _ = minicbor::encode::<WordIndex, &mut Vec<u8>>(
-            WordIndex { words: chunk },
-            word_index.as_mut(),
-        );
-
-        let hash = format!("{}_{}", language, full_hash(&word_index));
-        let mut short_hash = &hash[0..=(language.len() + 7)];
-
-        // If we hit a collision, extend one hash until we stop colliding
-        while word_indexes.contains_key(short_hash) {
-            let new_length = short_hash.len() + 1;
-            short_hash = &hash[0..=new_length];
+    encode_word_chunks(
+        chunks,
+        "",
+        &mut meta.index_chunks,
+        &mut word_indexes,
+        &language,
+    );
 
-            if short_hash.len() == hash.len() {
-                break;
-            }
-        }
-        word_indexes.insert(short_hash.to_string(), word_index);
-        meta.index_chunks[i].hash = short_hash.into();
+    for (field, field_word_map) in meta_field_word_maps {
+        let chunks = chunk_index(field_word_map, 20000);
+        let mut index_chunks = chunk_meta(&chunks);
+        encode_word_chunks(
+            chunks,
+            &field,
+            &mut index_chunks,
+            &mut word_indexes,
+            &language,
+        );
+        meta.meta_fields.push(MetaField {
+            field,
+            index_chunks,
+        });
     }
 
     let mut meta_index: Vec<u8> = Vec::new();
@@ -324,6 +364,155 @@ pub async fn build_indexes(
     })
 }
 
+/// Adds a page's positions for a word to the inverse index of all words
+fn pack_word(
+    word_map: &mut HashMap<String, PackedWord>,
+    word: String,
+    mut positions: Vec<FossickedWord>,
+    page_number: usize,
+) {
+    // A page weight of 1 is encoded as 25. Since most words should be this weight,
+    // we want to sort them to be first in the locations array to reduce filesize
+    // when we inline weight changes
+    positions.sort_by_cached_key(|p| if p.weight == 25 { 0 } else { p.weight });
+
+    let mut current_weight = 25;
+    let mut weighted_positions = Vec::with_capacity(positions.len());
+    // Calculate our output list of positions with weights.
+    // This is a vec of page positions, with a change in weight for subsequent positions
+    // denoted by a negative integer.
+    positions
+        .into_iter()
+        .for_each(|FossickedWord { position, weight }| {
+            if weight != current_weight {
+                weighted_positions.extend([(weight as i32) * -1 - 1, position as i32]);
+                current_weight = weight;
+            } else {
+                weighted_positions.push(position as i32)
+            }
+        });
+
+    let packed_page = PackedPage {
+        page_number,
+        locs: weighted_positions,
+    };
+
+    match word_map.get_mut(&word) {
+        Some(packed) => packed.pages.push(packed_page),
+        None => {
+            word_map.insert(
+                word.clone(),
+                PackedWord {
+                    word,
+                    pages: vec![packed_page],
+                    form: String::new(),
+                },
+            );
+        }
+    }
+}
+
+/// Sets each word to the form it was most often written in before stemming,
+/// preferring the shortest form on a tie, so that completions can be shown as written
+fn set_word_forms(
+    word_map: &mut HashMap<String, PackedWord>,
+    word_forms: HashMap<String, HashMap<String, u32>>,
+) {
+    for (word, forms) in word_forms {
+        let Some(packed) = word_map.get_mut(&word) else {
+            continue;
+        };
+        let form = forms
+            .into_iter()
+            .max_by(|(a, a_count), (b, b_count)| {
+                a_count
+                    .cmp(b_count)
+                    .then_with(|| b.len().cmp(&a.len()))
+                    .then_with(|| b.cmp(a))
+            })
+            .map(|(form, _)| form);
+        if let Some(form) = form.filter(|form| *form != word) {
+            packed.form = form;
+        }
+    }
+}
+
+/// Encodes each chunk of words into a word index file,
+/// filling in the hash of that file on the matching `MetaChunk`.
+/// `field` is the metadata field these words were indexed from, or empty for page content.
+fn encode_word_chunks(
+    chunks: Vec<Vec<PackedWord>>,
+    field: &str,
+    index_chunks: &mut [MetaChunk],
+    word_indexes: &mut HashMap<String, Vec<u8>>,
+    language: &str,
+) {
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        let mut word_index: Vec<u8> = Vec::new();
+        let _ = minicbor::encode::<WordIndex, &mut Vec<u8>>(
+            WordIndex {
+                words: chunk,
+                field: field.into(),
+            },
+            word_index.as_mut(),
+        );
+
+        let hash = format!("{}_{}", language, full_hash(&word_index));
+        let mut short_hash = &hash[0..=(language.len() + 7)];
+
+        // If we hit a collision, extend one hash until we stop colliding
+        while word_indexes.contains_key(short_hash) {
+            let new_length = short_hash.len() + 1;
+            short_hash = &hash[0..=new_length];
+
+            if short_hash.len() == hash.len() {
+                break;
+            }
+        }
+        word_indexes.insert(short_hash.to_string(), word_index);
+        index_chunks[i].hash = short_hash.into();
+    }
+}
+
+/// Stems the configured synonym groups for this language, and flattens them
+/// into the terms that each searched term should expand to
+fn build_synonyms(language: &str, options: &SearchOptions) -> Vec<MetaSynonym> {
+    let mut synonyms: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut add_synonym = |from: &str, to: &str| {
+        let from = stem_words(from, language, options).join(" ");
+        let to = stem_words(to, language, options).join(" ");
+        if from.is_empty() || to.is_empty() || from == to {
+            return;
+        }
+        let expansions = synonyms.entry(from).or_default();
+        if !expansions.contains(&to) {
+            expansions.push(to);
+        }
+    };
+
+    for group in &options.synonyms {
+        match group {
+            SynonymGroup::Equivalent(terms) => {
+                for from in terms {
+                    for to in terms {
+                        add_synonym(from, to);
+                    }
+                }
+            }
+            SynonymGroup::OneWay { from, to } => {
+                for to in to {
+                    add_synonym(from, to);
+                }
+            }
+        }
+    }
+
+    synonyms
+        .into_iter()
+        .map(|(term, synonyms)| MetaSynonym { term, synonyms })
+        .collect()
+}
+
 fn chunk_index(word_map: HashMap<String, PackedWord>, chunk_size: usize) -> Vec<Vec<PackedWord>> {
     // TODO: Use ye olde BTree
     let mut words = word_map
@@ -387,6 +576,146 @@ fn get_prefixes((a, b): (&str, &str)) -> (String, String) {
     (a_prefix, b_prefix)
 }
 
+/// Returns the position of each filter value on a number line, when every value
+/// is either a number or an ISO 8601 date (as seconds since the epoch).
+/// Returns nothing for filters that can't be compared by range.
+fn filter_value_orders(values: &[PackedValue]) -> Vec<f64> {
+    let numbers: Option<Vec<f64>> = values
+        .iter()
+        .map(|v| lexical_core::parse::<f64>(v.value.trim().as_bytes()).ok())
+        .collect();
+    if let Some(numbers) = numbers {
+        return numbers;
+    }
+
+    values
+        .iter()
+        .map(|v| parse_iso_date(&v.value))
+        .collect::<Option<Vec<f64>>>()
+        .unwrap_or_default()
+}
+
+/// Adds a value for each level above a nested filter value that isn't already
+/// a value of its own, so that every level of the tree can be filtered to.
+fn add_filter_ancestors(values: &mut Vec<PackedValue>, separator: &str) {
+    let mut known: HashSet<String> = values.iter().map(|v| v.value.clone()).collect();
+    let mut ancestors = vec![];
+    for value in values.iter() {
+        let mut path = value.value.as_str();
+        while let Some((parent, _)) = path.rsplit_once(separator) {
+            if known.insert(parent.to_owned()) {
+                ancestors.push(PackedValue {
+                    value: parent.to_owned(),
+                    pages: vec![],
+                });
+            }
+            path = parent;
+        }
+    }
+    values.extend(ancestors);
+}
+
+/// Returns the position of each filter value's parent in `values`, offset by one
+/// so that top-level values have a parent of zero.
+/// Returns nothing for filters without any nested values.
+fn filter_value_parents(values: &[PackedValue], separator: Option<&str>) -> Vec<u32> {
+    let Some(separator) = separator else {
+        return vec![];
+    };
+    let positions: HashMap<&str, usize> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (v.value.as_str(), i))
+        .collect();
+    let parents: Vec<u32> = values
+        .iter()
+        .map(|v| {
+            v.value
+                .rsplit_once(separator)
+                .and_then(|(parent, _)| positions.get(parent))
+                .map_or(0, |i| *i as u32 + 1)
+        })
+        .collect();
+
+    if parents.iter().all(|p| *p == 0) {
+        vec![]
+    } else {
+        parents
+    }
+}
+
+/// Reads the dates of the metadata fields configured in `recency_meta_fields`,
+/// dropping any field with a value that isn't a date,
+/// so that search results can be boosted by how recent they are
+fn build_meta_dates<'a>(
+    page_meta: impl Iterator<Item = (usize, &'a BTreeMap<String, String>)>,
+    options: &SearchOptions,
+) -> Vec<MetaDates> {
+    if options.recency_meta_fields.is_empty() {
+        return vec![];
+    }
+
+    let mut fields: BTreeMap<&str, Option<MetaDates>> = BTreeMap::new();
+    for (page_number, meta) in page_meta {
+        for (field, value) in meta {
+            if !options.recency_meta_fields.contains(field) {
+                continue;
+            }
+            let entry = fields.entry(field.as_str()).or_insert_with(|| {
+                Some(MetaDates {
+                    field: field.clone(),
+                    pages: vec![],
+                    dates: vec![],
+                })
+            });
+            let Some(meta_dates) = entry else {
+                continue;
+            };
+            match parse_date_sort(value, options) {
+                Some(date) => {
+                    meta_dates.pages.push(page_number);
+                    meta_dates.dates.push(date);
+                }
+                None => *entry = None,
+            }
+        }
+    }
+
+    fields.into_values().flatten().collect()
+}
+
+fn parse_sort_value(sort_type: &SortType, value: &str, options: &SearchOptions) -> SortValue {
+    match sort_type {
+        SortType::String => SortValue::String(value.to_owned()),
+        SortType::Number => SortValue::Number(
+            parse_int_sort(value)
+                .map(|i| i as f32)
+                .unwrap_or_else(|| parse_float_sort(value).unwrap_or_default()),
+        ),
+        SortType::Date => SortValue::Date(parse_date_sort(value, options).unwrap_or_default()),
+    }
+}
+
+impl SortValue {
+    fn compare(&self, other: &SortValue, collator: &LanguageCollator) -> std::cmp::Ordering {
+        match (self, other) {
+            (SortValue::String(a), SortValue::String(b)) => collator.compare(a, b),
+            (SortValue::Number(a), SortValue::Number(b)) => a.total_cmp(b),
+            (SortValue::Date(a), SortValue::Date(b)) => a.total_cmp(b),
+            _ => std::cmp::Ordering::Equal,
+        }
+    }
+}
+
+fn parse_date_sort(value: &str, options: &SearchOptions) -> Option<f64> {
+    parse_iso_date(value).or_else(|| {
+        options
+            .sort_date_formats
+            .iter()
+            .find_map(|format| parse_formatted_date(value, format))
+    })
+}
+
 fn parse_int_sort(value: &str) -> Option<i32> {
     lexical_core::parse::<i32>(value.as_bytes()).ok()
 }
@@ -413,6 +742,7 @@ mod tests {
                         PackedWord {
                             word: word.into(),
                             pages: vec![page],
+                            form: String::new(),
                         },
                     );
                 }
@@ -442,6 +772,33 @@ mod tests {
         assert_eq!(chunks[2][0].word, "peach");
     }
 
+    #[test]
+    fn most_common_word_forms() {
+        let mut words = test_words();
+        let forms = |forms: &[(&str, u32)]| -> HashMap<String, u32> {
+            forms.iter().map(|(f, c)| (f.to_string(), *c)).collect()
+        };
+        let word_forms = HashMap::from([
+            ("apple".to_string(), forms(&[("apples", 3), ("apple", 2)])),
+            (
+                "apricot".to_string(),
+                forms(&[("apricot", 4), ("apricots", 1)]),
+            ),
+            (
+                "banana".to_string(),
+                forms(&[("bananas", 1), ("bananaz", 1)]),
+            ),
+            ("plum".to_string(), forms(&[("plums", 1)])),
+        ]);
+        set_word_forms(&mut words, word_forms);
+
+        assert_eq!(words["apple"].form, "apples");
+        assert_eq!(words["apricot"].form, "");
+        assert_eq!(words["banana"].form, "bananas");
+        assert_eq!(words["peach"].form, "");
+        assert!(!words.contains_key("plum"));
+    }
+
     #[test]
     fn build_chunk_meta() {
         let chunks = chunk_index(test_words(), 8);
@@ -493,4 +850,125 @@ mod tests {
             ("catha".into(), "cath".into())
         );
     }
+
+    #[test]
+    fn stemmed_synonyms() {
+        use crate::PagefindInboundConfig;
+        use twelf::Layer;
+
+        std::env::set_var("PAGEFIND_SOURCE", "somewhere");
+        let config =
+            PagefindInboundConfig::with_layers(&[Layer::Env(Some("PAGEFIND_".into()))]).unwrap();
+        let mut options = SearchOptions::load(config).unwrap();
+        options.synonyms = vec![
+            SynonymGroup::Equivalent(vec!["K8s".into(), "Kubernetes".into()]),
+            SynonymGroup::OneWay {
+                from: "js".into(),
+                to: vec!["JavaScript".into(), "ECMA Script".into()],
+            },
+        ];
+
+        assert_eq!(
+            build_synonyms("en", &options),
+            vec![
+                MetaSynonym {
+                    term: "js".into(),
+                    synonyms: vec!["javascript".into(), "ecma script".into()],
+                },
+                MetaSynonym {
+                    term: "k8s".into(),
+                    synonyms: vec!["kubernet".into()],
+                },
+                MetaSynonym {
+                    term: "kubernet".into(),
+                    synonyms: vec!["k8s".into()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn meta_field_dates() {
+        use crate::PagefindInboundConfig;
+        use twelf::Layer;
+
+        std::env::set_var("PAGEFIND_SOURCE", "somewhere");
+        let config =
+            PagefindInboundConfig::with_layers(&[Layer::Env(Some("PAGEFIND_".into()))]).unwrap();
+        let mut options = SearchOptions::load(config).unwrap();
+
+        let page_meta: Vec<BTreeMap<String, String>> = vec![
+            [
+                ("title", "Hello"),
+                ("published", "2024-01-02"),
+                ("updated", "2024-02-01"),
+            ],
+            [
+                ("title", "World"),
+                ("published", "March 3, 2024"),
+                ("updated", "Soon"),
+            ],
+            [
+                ("title", "Again"),
+                ("published", "2024-01-01"),
+                ("updated", "2024-02-02"),
+            ],
+        ]
+        .into_iter()
+        .map(|meta| meta.map(|(k, v)| (k.to_string(), v.to_string())).into())
+        .collect();
+
+        assert_eq!(
+            build_meta_dates(page_meta.iter().enumerate(), &options),
+            vec![]
+        );
+
+        options.recency_meta_fields = vec!["published".into(), "updated".into()];
+        assert_eq!(
+            build_meta_dates(page_meta.iter().enumerate(), &options),
+            vec![MetaDates {
+                field: "published".into(),
+                pages: vec![0, 1, 2],
+                dates: vec![1704153600.0, 1709424000.0, 1704067200.0],
+            }]
+        );
+    }
+
+    #[test]
+    fn nested_filter_values() {
+        let mut values = vec![
+            PackedValue {
+                value: "Guides > Deployment > AWS".into(),
+                pages: vec![1],
+            },
+            PackedValue {
+                value: "Guides".into(),
+                pages: vec![2],
+            },
+            PackedValue {
+                value: "Blog".into(),
+                pages: vec![3],
+            },
+        ];
+        add_filter_ancestors(&mut values, " > ");
+
+        assert_eq!(
+            values
+                .iter()
+                .map(|v| (v.value.as_str(), v.pages.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Guides > Deployment > AWS", vec![1]),
+                ("Guides", vec![2]),
+                ("Blog", vec![3]),
+                ("Guides > Deployment", vec![]),
+            ]
+        );
+        assert_eq!(filter_value_parents(&values, Some(" > ")), vec![4, 0, 0, 2]);
+        assert_eq!(
+            filter_value_parents(&values[1..3], Some(" > ")),
+            Vec::<u32>::new()
+        );
+        assert_eq!(filter_value_parents(&values, None), Vec::<u32>::new());
+    }
 }
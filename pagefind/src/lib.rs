@@ -1,14 +1,15 @@
 use std::{cmp::Ordering, path::PathBuf};
 
 use anyhow::{bail, Result};
-use fossick::{FossickedData, Fossicker};
+use fossick::{records::parse_records, FossickedData, Fossicker};
 use futures::future::join_all;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use index::PagefindIndexes;
 use options::{PagefindInboundConfig, SearchOptions};
 use output::SyntheticFile;
 pub use service::api;
-use wax::{Glob, WalkEntry};
+use sitemap::read_sitemap;
+use wax::{Glob, Pattern};
 
 use crate::index::build_indexes;
 
@@ -21,8 +22,10 @@ pub mod options;
 mod output;
 mod playground;
 pub mod runner;
+pub mod search;
 mod serve;
 mod service;
+mod sitemap;
 mod utils;
 
 const PAGEFIND_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -42,43 +45,157 @@ impl SearchState {
         }
     }
 
-    pub async fn walk_for_files(&mut self, dir: PathBuf, glob: String) -> Result<Vec<Fossicker>> {
+    pub async fn walk_for_files(
+        &mut self,
+        dir: PathBuf,
+        globs: &[String],
+    ) -> Result<Vec<Fossicker>> {
         let log = &self.options.logger;
 
         log.status("[Walking source directory]");
-        if let Ok(glob) = Glob::new(&glob) {
-            Ok(glob
-                .walk(&dir)
-                .filter_map(Result::ok)
-                .map(WalkEntry::into_path)
-                .map(|file_path| Fossicker::new_relative_to(file_path, dir.clone()))
-                .collect())
-        } else {
-            log.error(format!(
-                "Error: Provided glob \"{}\" did not parse as a valid glob.",
-                self.options.glob
+        let exclude_globs = self
+            .options
+            .exclude_glob
+            .iter()
+            .map(|glob| self.parse_glob(glob, "exclude glob"))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut files = vec![];
+        let mut seen_files = HashSet::new();
+        let mut excluded_counts = vec![0; exclude_globs.len()];
+        for glob in globs {
+            let parsed_glob = self.parse_glob(glob, "glob")?;
+            let mut matched = 0;
+            // Exclude globs are checked during the same walk, rather than walking each of them
+            for entry in parsed_glob.walk(&dir).filter_map(Result::ok) {
+                matched += 1;
+                let excluded_by = exclude_globs
+                    .iter()
+                    .position(|exclude| exclude.is_match(entry.to_candidate_path()));
+                let file_path = entry.into_path();
+                if !seen_files.insert(file_path.clone()) {
+                    continue;
+                }
+                match excluded_by {
+                    Some(i) => excluded_counts[i] += 1,
+                    None => files.push(file_path),
+                }
+            }
+            log.v_info(format!(
+                "  * {glob} matched {matched} file{}",
+                plural!(matched)
             ));
-            bail!(
-                "Error: Provided glob \"{}\" did not parse as a valid glob.",
-                self.options.glob
+        }
+
+        for (glob, excluded) in self.options.exclude_glob.iter().zip(excluded_counts) {
+            log.v_info(format!(
+                "  * {glob} excluded {excluded} file{}",
+                plural!(excluded)
+            ));
+        }
+
+        Ok(files
+            .into_iter()
+            .map(|file_path| Fossicker::new_relative_to(file_path, dir.clone()))
+            .collect())
+    }
+
+    fn parse_glob<'g>(&self, glob: &'g str, option: &str) -> Result<Glob<'g>> {
+        match Glob::new(glob) {
+            Ok(parsed_glob) => Ok(parsed_glob),
+            Err(_) => {
+                self.options.logger.error(format!(
+                    "Error: Provided {option} \"{glob}\" did not parse as a valid glob."
+                ));
+                bail!("Error: Provided {option} \"{glob}\" did not parse as a valid glob.");
+            }
+        }
+    }
+
+    pub async fn walk_sitemap(&mut self, dir: PathBuf, sitemap: PathBuf) -> Result<Vec<Fossicker>> {
+        let log = &self.options.logger;
+
+        log.status("[Reading sitemap]");
+        let found = match read_sitemap(&dir, &sitemap) {
+            Ok(found) => found,
+            Err(e) => {
+                log.error(format!("Error: {e:#}"));
+                bail!("Error: {e:#}");
+            }
+        };
+
+        if !found.missing.is_empty() {
+            log.warn(format!(
+                "{} URL{} in the sitemap did not match a file in the site directory, and will not be indexed. \n\
+                Run Pagefind with --verbose for more information.",
+                found.missing.len(),
+                plural!(found.missing.len())
+            ));
+            log.v_warn(
+                found
+                    .missing
+                    .iter()
+                    .map(|loc| format!("  * {loc}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
             );
         }
+
+        Ok(found
+            .pages
+            .into_iter()
+            .map(|page| {
+                let mut file = Fossicker::new_relative_to(page.file_path, dir.clone());
+                if let (Some(sort_key), Some(lastmod)) =
+                    (&self.options.sitemap_lastmod_sort, page.lastmod)
+                {
+                    file = file.with_default_sort(sort_key.clone(), lastmod);
+                }
+                if let (true, Some(priority)) = (self.options.sitemap_priority, page.priority) {
+                    // A priority of 0.5 is the sitemap default, and leaves the page weight unchanged
+                    file = file.with_weight_multiplier(priority * 2.0);
+                }
+                file
+            })
+            .collect())
     }
 
-    pub async fn fossick_many(&mut self, dir: PathBuf, glob: String) -> Result<usize> {
-        let files = self.walk_for_files(dir.clone(), glob).await?;
+    pub async fn fossick_many(&mut self, dir: PathBuf, globs: Vec<String>) -> Result<usize> {
+        let files = self.walk_for_files(dir.clone(), &globs).await?;
         let log = &self.options.logger;
 
         log.info(format!(
             "Found {} file{} matching {}",
             files.len(),
             plural!(files.len()),
-            self.options.glob
+            globs.join(", ")
         ));
+
+        Ok(self.fossick_files(files).await)
+    }
+
+    pub async fn fossick_sitemap(&mut self, dir: PathBuf, sitemap: PathBuf) -> Result<usize> {
+        let files = self.walk_sitemap(dir, sitemap.clone()).await?;
+        let log = &self.options.logger;
+
+        log.info(format!(
+            "Found {} page{} in {}",
+            files.len(),
+            plural!(files.len()),
+            sitemap.to_string_lossy()
+        ));
+
+        Ok(self.fossick_files(files).await)
+    }
+
+    async fn fossick_files(&mut self, files: Vec<Fossicker>) -> usize {
+        let log = &self.options.logger;
         log.status("[Parsing files]");
 
-        let results: Vec<_> = files
+        let pages = join_all(files.into_iter().map(|f| f.into_pages(&self.options))).await;
+        let results: Vec<_> = pages
             .into_iter()
+            .flatten()
             .map(|f| f.fossick(&self.options))
             .collect();
 
@@ -86,6 +203,44 @@ impl SearchState {
         self.fossicked_pages
             .extend(join_all(results).await.into_iter().flatten());
 
+        self.fossicked_pages.len() - existing_page_count
+    }
+
+    pub async fn fossick_records(&mut self, files: &[PathBuf]) -> Result<usize> {
+        if files.is_empty() {
+            return Ok(0);
+        }
+        let log = &self.options.logger;
+
+        log.status("[Reading custom records]");
+        let mut records = vec![];
+        for file in files {
+            let path = file.to_string_lossy();
+            let contents = match tokio::fs::read_to_string(file).await {
+                Ok(contents) => contents,
+                Err(e) => bail!("Failed to read custom records file {path}: {e}"),
+            };
+            let file_records = match parse_records(file, &contents) {
+                Ok(records) => records,
+                Err(e) => bail!("Failed to parse custom records file {path}: {e:#}"),
+            };
+            log.info(format!(
+                "Found {} record{} in {path}",
+                file_records.len(),
+                plural!(file_records.len())
+            ));
+            records.extend(file_records);
+        }
+
+        let results: Vec<_> = records
+            .into_iter()
+            .map(|record| record.into_fossicker(&self.options).fossick(&self.options))
+            .collect();
+
+        let existing_page_count = self.fossicked_pages.len();
+        self.fossicked_pages
+            .extend(join_all(results).await.into_iter().flatten());
+
         Ok(self.fossicked_pages.len() - existing_page_count)
     }
 
@@ -1,14 +1,15 @@
 //! Configuration that can be supplied to the `api` module when using Pagefind as a service.
 
 use anyhow::{bail, Result};
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use rust_patch::Patch;
-use serde::{Deserialize, Serialize};
-use std::{env, path::PathBuf};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{collections::HashMap, env, path::PathBuf};
 use twelf::config;
 use typed_builder::TypedBuilder;
 
 use crate::{
+    fossick::handlers::{parse_file_handler, FileHandler},
     logging::{LogLevel, Logger},
     utils::WORD_SYMBOLS,
 };
@@ -76,11 +77,49 @@ pub(crate) struct PagefindInboundConfig {
 
     #[clap(
         long,
-        help = "The file glob Pagefind uses to find HTML files. Defaults to \"**/*.{html}\""
+        help = "The file glob Pagefind uses to find HTML files. Defaults to \"**/*.{html}\". A list of globs can be provided in a configuration file."
     )]
     #[clap(required = false)]
     #[serde(default = "defaults::default_glob")]
-    pub(crate) glob: String,
+    pub(crate) glob: Globs,
+
+    #[clap(
+        long,
+        help = "A file glob for files Pagefind should skip, even when they match the glob option, e.g. \"drafts/**\". A list of globs can be provided in a configuration file."
+    )]
+    #[clap(required = false)]
+    #[serde(default)]
+    pub(crate) exclude_glob: Globs,
+
+    #[clap(
+        long,
+        help = "Read files with an extension using a handler, e.g. \"log=text\". Handlers are html, markdown, text, or xml. A list can be provided in a configuration file."
+    )]
+    #[clap(required = false)]
+    #[serde(default)]
+    pub(crate) file_handlers: Vec<String>,
+
+    #[clap(
+        long,
+        help = "Index the pages listed in this sitemap instead of the files matched by the glob, e.g. \"sitemap.xml\". Relative to the site directory. Sitemap indexes are followed."
+    )]
+    #[clap(required = false)]
+    pub(crate) sitemap: Option<String>,
+
+    #[clap(
+        long,
+        help = "When indexing from a sitemap, store the <lastmod> date of each page under this sort key, e.g. \"date\""
+    )]
+    #[clap(required = false)]
+    pub(crate) sitemap_lastmod_sort: Option<String>,
+
+    #[clap(
+        long,
+        help = "When indexing from a sitemap, weight each page by its <priority>. The default priority of 0.5 leaves a page's weight unchanged."
+    )]
+    #[clap(required = false)]
+    #[serde(default = "defaults::default_false")]
+    pub(crate) sitemap_priority: bool,
 
     #[clap(
         long,
@@ -96,6 +135,59 @@ pub(crate) struct PagefindInboundConfig {
     #[clap(required = false)]
     pub(crate) include_characters: Option<String>,
 
+    #[clap(
+        long,
+        help = "Metadata fields that should be indexed as their own searchable fields, e.g. \"title\". Searched using the `title:term` syntax."
+    )]
+    #[clap(required = false)]
+    #[serde(default)]
+    pub(crate) index_meta_fields: Vec<String>,
+
+    #[clap(
+        long,
+        help = "Split filter values into nested levels on this separator, e.g. \" > \" for \"Guides > Deployment > AWS\". Filtering to a level includes every level nested beneath it."
+    )]
+    #[clap(required = false)]
+    pub(crate) filter_separator: Option<String>,
+
+    #[clap(
+        long,
+        help = "Date formats to recognize in sort values, in addition to ISO 8601 dates, e.g. \"%d/%m/%Y\". Defaults to \"%B %d, %Y\" and \"%d %B %Y\"."
+    )]
+    #[clap(required = false)]
+    #[serde(default = "defaults::default_sort_date_formats")]
+    pub(crate) sort_date_formats: Vec<String>,
+
+    #[clap(
+        long,
+        help = "Metadata fields holding dates, e.g. \"published\", that search results can be boosted by how recent they are. Sorts can always be used for recency."
+    )]
+    #[clap(required = false)]
+    #[serde(default)]
+    pub(crate) recency_meta_fields: Vec<String>,
+
+    #[clap(
+        long,
+        help = "Path to a JSON file of synonym groups to expand search terms with. Relative to the current working directory."
+    )]
+    #[clap(required = false)]
+    pub(crate) synonyms_file: Option<String>,
+
+    #[clap(
+        long,
+        help = "Path to a JSON file of stop words for each language, replacing the built-in lists. Relative to the current working directory."
+    )]
+    #[clap(required = false)]
+    pub(crate) stop_words_file: Option<String>,
+
+    #[clap(
+        long,
+        help = "Paths to JSON or JSON Lines files of custom records to index alongside the site. Relative to the current working directory."
+    )]
+    #[clap(required = false)]
+    #[serde(default)]
+    pub(crate) custom_records: Vec<String>,
+
     #[clap(
         long,
         help = "Serve the source directory after creating the search index"
@@ -104,6 +196,14 @@ pub(crate) struct PagefindInboundConfig {
     #[serde(default = "defaults::default_false")]
     pub(crate) serve: bool,
 
+    #[clap(
+        long,
+        help = "When serving, also serve a JSON search endpoint at /pagefind/api/search, backed by the search index that was just built"
+    )]
+    #[clap(required = false)]
+    #[serde(default = "defaults::default_false")]
+    pub(crate) serve_api: bool,
+
     #[clap(
         long,
         short,
@@ -160,6 +260,74 @@ pub(crate) struct PagefindInboundConfig {
     #[clap(required = false, hide = true)]
     #[serde(default = "defaults::default_false")]
     pub(crate) service: bool,
+
+    #[clap(subcommand)]
+    #[serde(skip)]
+    pub(crate) command: Option<PagefindCommand>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum PagefindCommand {
+    #[clap(about = "Search a Pagefind bundle that has already been built")]
+    Search(SearchCommandConfig),
+}
+
+/// Options for `pagefind search`, which searches a bundle that has already been built
+#[derive(Args, Debug, Clone)]
+pub(crate) struct SearchCommandConfig {
+    #[clap(help = "The search query. Leave empty to list every page that matches the filters")]
+    pub(crate) query: Option<String>,
+
+    #[clap(
+        long,
+        help = "The location of the built search bundle, e.g. \"public/pagefind\""
+    )]
+    pub(crate) bundle: PathBuf,
+
+    #[clap(
+        long,
+        help = "The language index to search. Defaults to the language with the most pages. Expects an ISO 639-1 code."
+    )]
+    pub(crate) language: Option<String>,
+
+    #[clap(
+        long,
+        help = "Filters to apply, as a JSON object, e.g. '{\"category\": \"Docs\"}'"
+    )]
+    pub(crate) filter: Option<String>,
+
+    #[clap(long, help = "Sorts to apply, e.g. \"date desc, relevance\"")]
+    pub(crate) sort: Option<String>,
+
+    #[clap(
+        long,
+        default_value = "last",
+        help = "Where to place results without a value for the sort: first, last, or drop"
+    )]
+    pub(crate) sort_missing: String,
+
+    #[clap(
+        long,
+        default_value_t = 0,
+        help = "The number of typos to allow in each search term"
+    )]
+    pub(crate) fuzzy: u8,
+
+    #[clap(long, default_value_t = 0, help = "The number of results to skip")]
+    pub(crate) offset: usize,
+
+    #[clap(long, default_value_t = 10, help = "The number of results to show")]
+    pub(crate) limit: usize,
+
+    #[clap(
+        long,
+        default_value_t = 30,
+        help = "The number of words in each excerpt"
+    )]
+    pub(crate) excerpt_length: usize,
+
+    #[clap(long, help = "Output the results as JSON")]
+    pub(crate) json: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, Patch, TypedBuilder)]
@@ -187,6 +355,15 @@ pub struct PagefindServiceConfig {
     #[patch(as_option)]
     /// Ignore any detected languages and index the whole site as a single language. Expects an ISO 639-1 code.
     pub(crate) force_language: Option<String>,
+    /// Metadata fields that should be indexed as their own searchable fields, e.g. "title".
+    pub(crate) index_meta_fields: Option<Vec<String>>,
+    #[patch(as_option)]
+    /// Split filter values into nested levels on this separator, e.g. " > ".
+    pub(crate) filter_separator: Option<String>,
+    /// Date formats to recognize in sort values, in addition to ISO 8601 dates, e.g. "%d/%m/%Y".
+    pub(crate) sort_date_formats: Option<Vec<String>>,
+    /// Metadata fields holding dates, e.g. "published", that search results can be boosted by how recent they are.
+    pub(crate) recency_meta_fields: Option<Vec<String>>,
     /// Print verbose logging while indexing the site. Does not impact the web-facing search.
     pub(crate) verbose: Option<bool>,
     #[patch(as_option)]
@@ -199,18 +376,55 @@ pub struct PagefindServiceConfig {
 }
 
 mod defaults {
+    use super::Globs;
+
     pub fn default_bundle_dir() -> String {
         "pagefind".into()
     }
     pub fn default_root_selector() -> String {
         "html".into()
     }
-    pub fn default_glob() -> String {
-        "**/*.{html}".into()
+    pub fn default_glob() -> Globs {
+        Globs(vec!["**/*.{html}".into()])
     }
     pub fn default_false() -> bool {
         false
     }
+    pub fn default_sort_date_formats() -> Vec<String> {
+        vec!["%B %d, %Y".into(), "%d %B %Y".into()]
+    }
+}
+
+/// A glob option, which takes a list of globs in configuration files but a single glob
+/// from the CLI or an environment variable. The CLI and environment values are never split
+/// on commas, as commas are part of brace expansions such as `**/*.{html,md}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub(crate) struct Globs(pub(crate) Vec<String>);
+
+impl From<String> for Globs {
+    fn from(glob: String) -> Self {
+        Self(vec![glob])
+    }
+}
+
+impl<'de> Deserialize<'de> for Globs {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum OneOrMany {
+            One(String),
+            Many(Vec<String>),
+        }
+
+        Ok(match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(glob) => glob.into(),
+            OneOrMany::Many(globs) => Self(globs),
+        })
+    }
 }
 
 // The configuration object used internally
@@ -221,9 +435,21 @@ pub(crate) struct SearchOptions {
     pub(crate) bundle_output: PathBuf,
     pub(crate) root_selector: String,
     pub(crate) exclude_selectors: Vec<String>,
-    pub(crate) glob: String,
+    pub(crate) glob: Vec<String>,
+    pub(crate) exclude_glob: Vec<String>,
+    pub(crate) file_handlers: HashMap<String, FileHandler>,
+    pub(crate) sitemap: Option<PathBuf>,
+    pub(crate) sitemap_lastmod_sort: Option<String>,
+    pub(crate) sitemap_priority: bool,
     pub(crate) force_language: Option<String>,
     pub(crate) include_characters: Vec<char>,
+    pub(crate) index_meta_fields: Vec<String>,
+    pub(crate) filter_separator: Option<String>,
+    pub(crate) sort_date_formats: Vec<String>,
+    pub(crate) recency_meta_fields: Vec<String>,
+    pub(crate) synonyms: Vec<SynonymGroup>,
+    pub(crate) stop_words: HashMap<String, Vec<String>>,
+    pub(crate) custom_records: Vec<PathBuf>,
     pub(crate) version: &'static str,
     pub(crate) logger: Logger,
     pub(crate) keep_index_url: bool,
@@ -232,6 +458,16 @@ pub(crate) struct SearchOptions {
     pub(crate) config_warnings: ConfigWarnings,
 }
 
+/// A group of synonyms as written in the synonyms file.
+/// A list of terms are all interchangeable, whereas an object
+/// only expands the `from` term to each of the `to` terms.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum SynonymGroup {
+    Equivalent(Vec<String>),
+    OneWay { from: String, to: Vec<String> },
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct ConfigWarnings {
     pub(crate) unconfigured_bundle_output: bool,
@@ -295,15 +531,91 @@ impl SearchOptions {
                 include_characters.extend(custom_include_characters.chars());
             }
 
+            let synonyms = match config.synonyms_file {
+                Some(synonyms_file) => {
+                    let synonyms_path = working_directory.join(synonyms_file);
+                    let contents = match std::fs::read_to_string(&synonyms_path) {
+                        Ok(contents) => contents,
+                        Err(e) => bail!(
+                            "Failed to read synonyms file {}: {}",
+                            synonyms_path.to_string_lossy(),
+                            e
+                        ),
+                    };
+                    match serde_json::from_str(&contents) {
+                        Ok(synonyms) => synonyms,
+                        Err(e) => bail!(
+                            "Failed to parse synonyms file {}: {}",
+                            synonyms_path.to_string_lossy(),
+                            e
+                        ),
+                    }
+                }
+                None => vec![],
+            };
+
+            let stop_words = match config.stop_words_file {
+                Some(stop_words_file) => {
+                    let stop_words_path = working_directory.join(stop_words_file);
+                    let contents = match std::fs::read_to_string(&stop_words_path) {
+                        Ok(contents) => contents,
+                        Err(e) => bail!(
+                            "Failed to read stop words file {}: {}",
+                            stop_words_path.to_string_lossy(),
+                            e
+                        ),
+                    };
+                    match serde_json::from_str(&contents) {
+                        Ok(stop_words) => stop_words,
+                        Err(e) => bail!(
+                            "Failed to parse stop words file {}: {}",
+                            stop_words_path.to_string_lossy(),
+                            e
+                        ),
+                    }
+                }
+                None => HashMap::new(),
+            };
+
+            let custom_records = config
+                .custom_records
+                .iter()
+                .map(|path| working_directory.join(path))
+                .collect();
+
+            let mut file_handlers = HashMap::new();
+            for entry in &config.file_handlers {
+                match parse_file_handler(entry) {
+                    Ok((extension, handler)) => {
+                        file_handlers.insert(extension, handler);
+                    }
+                    Err(e) => bail!("Invalid file handler: {e}"),
+                }
+            }
+
+            let sitemap = config.sitemap.map(|sitemap| site_source.join(sitemap));
+
             Ok(Self {
                 working_directory,
                 site_source,
                 bundle_output,
                 root_selector: config.root_selector,
                 exclude_selectors: config.exclude_selectors,
-                glob: config.glob,
+                glob: config.glob.0,
+                exclude_glob: config.exclude_glob.0,
+                file_handlers,
+                sitemap,
+                sitemap_lastmod_sort: config.sitemap_lastmod_sort,
+                sitemap_priority: config.sitemap_priority,
                 force_language: config.force_language,
                 include_characters,
+                index_meta_fields: config.index_meta_fields,
+                filter_separator: config.filter_separator.filter(|s| !s.trim().is_empty()),
+                sort_date_formats: config.sort_date_formats,
+                recency_meta_fields: config.recency_meta_fields,
+                synonyms,
+                stop_words,
+                custom_records,
                 version: env!("CARGO_PKG_VERSION"),
                 logger: Logger::new(
                     log_level,
@@ -355,3 +667,63 @@ impl ConfigWarnings {
         strings
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+    use twelf::Layer;
+
+    #[test]
+    fn reading_globs() {
+        let matches = PagefindInboundConfig::command().get_matches_from([
+            "pagefind",
+            "--glob",
+            "**/*.{html,md}",
+            "--exclude-glob",
+            "{drafts,archive}/**",
+        ]);
+        let config = PagefindInboundConfig::with_layers(&[Layer::Clap(matches)]).unwrap();
+        assert_eq!(config.glob, Globs(vec!["**/*.{html,md}".into()]));
+        assert_eq!(
+            config.exclude_glob,
+            Globs(vec!["{drafts,archive}/**".into()])
+        );
+
+        let globs: Globs = serde_json::from_str(r#"["**/*.html", "**/*.{md,txt}"]"#).unwrap();
+        assert_eq!(
+            globs,
+            Globs(vec!["**/*.html".into(), "**/*.{md,txt}".into()])
+        );
+
+        let matches = PagefindInboundConfig::command().get_matches_from(["pagefind"]);
+        let config = PagefindInboundConfig::with_layers(&[Layer::Clap(matches)]).unwrap();
+        assert_eq!(config.glob, defaults::default_glob());
+        assert_eq!(config.exclude_glob, Globs::default());
+    }
+
+    #[test]
+    fn reading_the_search_command() {
+        let matches = PagefindInboundConfig::command().get_matches_from([
+            "pagefind",
+            "--verbose",
+            "search",
+            "static search",
+            "--bundle",
+            "public/pagefind",
+            "--limit",
+            "5",
+        ]);
+        let PagefindCommand::Search(config) = PagefindCommand::from_arg_matches(&matches).unwrap();
+        assert_eq!(config.query.as_deref(), Some("static search"));
+        assert_eq!(config.bundle, PathBuf::from("public/pagefind"));
+        assert_eq!(config.limit, 5);
+        assert_eq!(config.sort_missing, "last");
+
+        let matches =
+            PagefindInboundConfig::command().get_matches_from(["pagefind", "--site", "search"]);
+        assert!(matches.subcommand().is_none());
+        let config = PagefindInboundConfig::with_layers(&[Layer::Clap(matches)]).unwrap();
+        assert_eq!(config.site, "search");
+    }
+}
@@ -120,6 +120,7 @@ async fn write_common(
             )
         })),
         include_characters: options.include_characters.clone(),
+        index_meta_fields: options.index_meta_fields.clone(),
     };
     let encoded_entry_meta = serde_json::to_string(&entry_meta).unwrap();
 
@@ -1,11 +1,14 @@
 //! The full Pagefind indexer as run by the CLI.
 
-use crate::options::SearchOptions;
+use crate::options::{PagefindCommand, SearchCommandConfig, SearchOptions};
+use crate::search::{parse_sorts, Bundle, BundleSearchOptions, SearchResults};
 use crate::serve;
 
 use super::service::run_service;
 use super::{PagefindInboundConfig, SearchState};
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
+use clap::FromArgMatches;
+use console::style;
 use std::path::PathBuf;
 use std::time::Instant;
 use twelf::reexports::clap::CommandFactory;
@@ -18,7 +21,8 @@ const CONFIGS: &[&str] = &[
     "pagefind.toml",
 ];
 
-/// Runs the full Pagefind indexing process used by the Pagefind binary.
+/// Runs the full Pagefind indexing process used by the Pagefind binary,
+/// or the `pagefind search` subcommand when it is given.
 ///
 /// Will log to stdout/stderr.
 pub async fn run_indexer() -> Result<()> {
@@ -28,6 +32,12 @@ pub async fn run_indexer() -> Result<()> {
         // .ignore_errors(true)
         .get_matches();
 
+    // `pagefind search` queries a bundle that has already been built, rather than indexing a site
+    if matches.subcommand().is_some() {
+        let PagefindCommand::Search(config) = PagefindCommand::from_arg_matches(&matches)?;
+        return run_search(config);
+    }
+
     let mut config_layers = vec![];
 
     let configs: Vec<&str> = CONFIGS
@@ -74,10 +84,21 @@ pub async fn run_indexer() -> Result<()> {
                 let logger = runner.options.logger.clone();
 
                 runner.log_start();
-                // TODO: Error handling
-                _ = runner
-                    .fossick_many(options.site_source.clone(), options.glob)
-                    .await;
+                match &options.sitemap {
+                    Some(sitemap) => {
+                        // A sitemap that can't be read shouldn't quietly write an empty index
+                        runner
+                            .fossick_sitemap(options.site_source.clone(), sitemap.clone())
+                            .await?;
+                    }
+                    None => {
+                        // TODO: Error handling
+                        _ = runner
+                            .fossick_many(options.site_source.clone(), options.glob)
+                            .await;
+                    }
+                };
+                runner.fossick_records(&options.custom_records).await?;
 
                 let use_old_bundle = options.config_warnings.unconfigured_bundle_output
                     && runner
@@ -129,7 +150,8 @@ pub async fn run_indexer() -> Result<()> {
                 }
 
                 if config.serve {
-                    serve::serve_dir(PathBuf::from(options.site_source)).await;
+                    let search_bundle = config.serve_api.then(|| options.bundle_output.clone());
+                    serve::serve_dir(PathBuf::from(options.site_source), search_bundle).await;
                 }
                 Ok(())
             }
@@ -162,3 +184,103 @@ pub async fn run_indexer() -> Result<()> {
         }
     }
 }
+
+/// Searches a bundle that has already been built, as run by `pagefind search`.
+///
+/// Will print the results to stdout.
+fn run_search(config: SearchCommandConfig) -> Result<()> {
+    let mut bundle = match &config.language {
+        Some(language) => Bundle::open_language(&config.bundle, language)?,
+        None => Bundle::open(&config.bundle)?,
+    };
+    bundle.set_excerpt_length(config.excerpt_length);
+
+    let filters = config
+        .filter
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+        .context("The filter option should be a JSON object")?;
+    let options = BundleSearchOptions {
+        filters,
+        sort: parse_sorts(config.sort.as_deref().unwrap_or_default())?,
+        sort_missing: config.sort_missing.parse()?,
+        fuzzy: config.fuzzy,
+        offset: config.offset,
+        limit: Some(config.limit),
+        counts_only: false,
+    };
+    let results = bundle.search(config.query.as_deref(), &options)?;
+
+    if config.json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print_search_results(&config, &results);
+    }
+    Ok(())
+}
+
+fn print_search_results(config: &SearchCommandConfig, results: &SearchResults) {
+    let count = results.result_count;
+    let mut summary = match &config.query {
+        Some(query) => format!("Found {count} result{} for \"{query}\"", plural!(count)),
+        None => format!("Found {count} page{}", plural!(count)),
+    };
+    if results.unfiltered_result_count != count {
+        summary.push_str(&format!(
+            " ({} without filters)",
+            results.unfiltered_result_count
+        ));
+    }
+    println!("{}", style(summary).cyan().bold());
+
+    for (i, result) in results.results.iter().enumerate() {
+        let title = result.data.meta.get("title").unwrap_or(&result.data.url);
+        println!(
+            "\n{}. {} {}",
+            config.offset + i + 1,
+            style(title).bold(),
+            style(format!("(score {:.3})", result.score)).dim()
+        );
+        println!("   {}", style(&result.data.url).green());
+        println!("   {}", highlight_excerpt(&result.excerpt));
+    }
+
+    let shown = config.offset + results.results.len();
+    if shown < count {
+        println!(
+            "\nShowing results {} to {shown} of {count}. Use --offset and --limit to see more.",
+            config.offset + 1
+        );
+    }
+
+    if results.filters.values().any(|values| !values.is_empty()) {
+        println!("\n{}", style("Filters").cyan().bold());
+        for (filter, values) in &results.filters {
+            let values: Vec<String> = values
+                .iter()
+                .map(|(value, count)| format!("{value} ({count})"))
+                .collect();
+            println!("  {filter}: {}", values.join(", "));
+        }
+    }
+}
+
+/// Styles the `<mark>` elements of an excerpt for the terminal
+fn highlight_excerpt(excerpt: &str) -> String {
+    let mut highlighted = String::with_capacity(excerpt.len());
+    for (i, part) in excerpt.split("<mark>").enumerate() {
+        let (marked, rest) = match i {
+            0 => ("", part),
+            _ => part.split_once("</mark>").unwrap_or((part, "")),
+        };
+        highlighted.push_str(
+            &style(html_escape::decode_html_entities(marked))
+                .bold()
+                .underlined()
+                .to_string(),
+        );
+        highlighted.push_str(&html_escape::decode_html_entities(rest));
+    }
+    highlighted
+}
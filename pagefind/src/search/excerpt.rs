@@ -0,0 +1,103 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref WHITESPACE: Regex = Regex::new("[\r\n\\s]+").unwrap();
+}
+
+/// Finds the word to start an excerpt at, centering the excerpt on the densest region of matching words.
+/// Mirrors `calculate_excerpt_region` in the JS API, so excerpts match across both.
+pub fn calculate_excerpt_region(word_positions: &[(u32, f32)], excerpt_length: usize) -> usize {
+    let Some(last_word) = word_positions.iter().map(|(location, _)| *location).max() else {
+        return 0;
+    };
+
+    let mut words = vec![0.0; last_word as usize + 1];
+    for (location, balanced_score) in word_positions {
+        words[*location as usize] += balanced_score;
+    }
+
+    if words.len() <= excerpt_length {
+        return 0;
+    }
+
+    let mut densest: f32 = words[..excerpt_length].iter().sum();
+    let mut working_sum = densest;
+    let mut densest_at = vec![0];
+
+    for i in 0..words.len() {
+        let boundary = i + excerpt_length;
+        working_sum += words.get(boundary).copied().unwrap_or(0.0) - words[i];
+        if working_sum > densest {
+            densest = working_sum;
+            densest_at = vec![i];
+        } else if working_sum == densest && densest_at.last().map(|at| at + 1) == Some(i) {
+            densest_at.push(i);
+        }
+    }
+
+    densest_at[densest_at.len() / 2]
+}
+
+/// Builds an excerpt of `length` words from the content, wrapping matching words in `<mark>` elements.
+pub fn build_excerpt(content: &str, start: usize, length: usize, locations: &[u32]) -> String {
+    let is_zws_delimited = content.contains('\u{200B}');
+    let mut fragment_words: Vec<String> = if is_zws_delimited {
+        // If segmentation was run while indexing, count words by ZWS boundaries
+        content.split('\u{200B}').map(Into::into).collect()
+    } else {
+        WHITESPACE.split(content).map(Into::into).collect()
+    };
+
+    for location in locations {
+        let Some(word) = fragment_words.get_mut(*location as usize) else {
+            continue;
+        };
+        if word.starts_with("<mark>") {
+            // It's possible to have a word come up as multiple search hits
+            continue;
+        }
+        *word = format!("<mark>{word}</mark>");
+    }
+
+    let length = length.min(fragment_words.len());
+    let start = start.min(fragment_words.len() - length);
+
+    fragment_words[start..start + length]
+        .join(if is_zws_delimited { "" } else { " " })
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excerpt_regions() {
+        assert_eq!(calculate_excerpt_region(&[], 4), 0);
+        assert_eq!(calculate_excerpt_region(&[(1, 1.0), (2, 1.0)], 4), 0);
+        assert_eq!(
+            calculate_excerpt_region(&[(0, 1.0), (10, 1.0), (11, 1.0), (12, 2.0)], 4),
+            9
+        );
+    }
+
+    #[test]
+    fn building_excerpts() {
+        let content = "The quick brown fox jumps over the lazy dog";
+        assert_eq!(
+            build_excerpt(content, 2, 3, &[3, 3]),
+            "brown <mark>fox</mark> jumps"
+        );
+        assert_eq!(
+            build_excerpt(content, 8, 3, &[8]),
+            "the lazy <mark>dog</mark>"
+        );
+        assert_eq!(build_excerpt(content, 0, 30, &[]), content);
+        assert_eq!(
+            build_excerpt("一\u{200B}二\u{200B}三", 0, 2, &[1]),
+            "一<mark>二</mark>"
+        );
+    }
+}
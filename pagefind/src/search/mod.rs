@@ -0,0 +1,631 @@
+//! The native interface for searching a Pagefind bundle that has already been written to disk.
+//!
+//! Searches run through the same search index as the Pagefind WebAssembly,
+//! so results, ranking, filtering, and sorting match the JS API.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use pagefind::search::{Bundle, BundleSearchOptions};
+//!
+//! let mut bundle = Bundle::open("public/pagefind").expect("Bundle should be readable");
+//! let results = bundle
+//!     .search(Some("static search"), &BundleSearchOptions::default())
+//!     .expect("Search should succeed");
+//!
+//! println!("Found {} results", results.result_count);
+//! for result in results.results {
+//!     println!("{}: {}", result.data.url, result.score);
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::GzDecoder;
+use pagefind_web::{NormalizedQuery, SearchIndex, SearchRequest};
+use serde::{Deserialize, Serialize};
+
+pub use crate::fragments::{PageAnchorData, PageFragmentData};
+pub use pagefind_web::{
+    FilterCounts, NestedFilterCount, NestedFilterCounts, RankingWeights, RecencyDecay, RecencyField,
+};
+
+use crate::fossick::get_stemmer;
+
+mod excerpt;
+
+const DECOMPRESSED_SIGNATURE: &[u8] = b"pagefind_dcd";
+
+#[derive(Deserialize, Debug)]
+struct BundleEntry {
+    version: String,
+    languages: BTreeMap<String, BundleEntryLanguage>,
+    #[serde(default)]
+    include_characters: Vec<char>,
+    #[serde(default)]
+    index_meta_fields: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BundleEntryLanguage {
+    hash: String,
+    page_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// Where results without a value for the sort are placed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortMissing {
+    First,
+    #[default]
+    Last,
+    Drop,
+}
+
+impl FromStr for SortDirection {
+    type Err = anyhow::Error;
+
+    fn from_str(direction: &str) -> Result<Self> {
+        match direction {
+            "asc" => Ok(Self::Asc),
+            "desc" => Ok(Self::Desc),
+            _ => bail!("Unknown sort direction {direction}. Supported: [asc, desc]"),
+        }
+    }
+}
+
+impl FromStr for SortMissing {
+    type Err = anyhow::Error;
+
+    fn from_str(sort_missing: &str) -> Result<Self> {
+        match sort_missing {
+            "first" => Ok(Self::First),
+            "last" => Ok(Self::Last),
+            "drop" => Ok(Self::Drop),
+            _ => bail!("Unknown sortMissing value {sort_missing}. Supported: [first, last, drop]"),
+        }
+    }
+}
+
+/// Parses sorts written as a string, e.g. `"priority desc, date desc, relevance"`.
+/// Sorts without a direction are ascending, apart from `relevance`, which is descending.
+pub fn parse_sorts(sorts: &str) -> Result<Vec<(String, SortDirection)>> {
+    sorts
+        .split(',')
+        .filter_map(|sort| {
+            let mut parts = sort.split_whitespace();
+            let sort = parts.next()?;
+            let direction = match parts.next() {
+                Some(direction) => direction.parse(),
+                None if sort == "relevance" => Ok(SortDirection::Desc),
+                None => Ok(SortDirection::Asc),
+            };
+            Some(direction.map(|direction| (sort.to_string(), direction)))
+        })
+        .collect()
+}
+
+/// The options for a single search, mirroring the options of the JS API
+#[derive(Debug, Clone, Default)]
+pub struct BundleSearchOptions {
+    /// Filters in the same format as the JS API, e.g. `{"color": "red"}`
+    pub filters: Option<serde_json::Value>,
+    /// Sorts to apply in order, where each sort breaks the ties of the sorts before it
+    pub sort: Vec<(String, SortDirection)>,
+    pub sort_missing: SortMissing,
+    /// The number of typos to allow in each search term. Exact searches never match fuzzily.
+    pub fuzzy: u8,
+    pub offset: usize,
+    pub limit: Option<usize>,
+    /// Return the counts without any results
+    pub counts_only: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchResult {
+    pub id: String,
+    pub score: f32,
+    /// The locations of each matching word in the page content
+    pub words: Vec<u32>,
+    /// The region of the page content with the most matching words, with each match wrapped in `<mark>`
+    pub excerpt: String,
+    pub data: PageFragmentData,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchResults {
+    pub results: Vec<SearchResult>,
+    /// How many results matched the search and filters, before paginating
+    pub result_count: usize,
+    /// How many results matched the search if the filters were removed
+    pub unfiltered_result_count: usize,
+    /// The number of results under each filter value
+    pub filters: FilterCounts,
+    /// The number of results under each filter value if the filters were removed
+    pub total_filters: FilterCounts,
+}
+
+/// A Pagefind bundle on disk, holding the search index for one of its languages.
+pub struct Bundle {
+    path: PathBuf,
+    language: String,
+    include_characters: Vec<char>,
+    index_meta_fields: Vec<String>,
+    search_index: SearchIndex,
+    loaded_chunks: HashSet<String>,
+    loaded_filters: HashSet<String>,
+    excerpt_length: usize,
+}
+
+impl Bundle {
+    /// Open the bundle at the given path, searching the language with the most pages.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the bundle directory, containing `pagefind-entry.json`.
+    ///
+    /// # Returns
+    /// A Bundle ready to search if successful, otherwise an Error.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_language(path, "")
+    }
+
+    /// Open the bundle at the given path, searching the given language.
+    /// Falls back to the base language (e.g. `pt` for `pt-br`), and then to the language with the most pages,
+    /// the same way the JS API picks an index.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the bundle directory, containing `pagefind-entry.json`.
+    /// * `language` - The language to search. Expects an ISO 639-1 code.
+    ///
+    /// # Returns
+    /// A Bundle ready to search if successful, otherwise an Error.
+    pub fn open_language(path: impl AsRef<Path>, language: &str) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entry_path = path.join("pagefind-entry.json");
+        let entry = fs::read_to_string(&entry_path)
+            .with_context(|| format!("Failed to read {}", entry_path.display()))?;
+        let entry: BundleEntry = serde_json::from_str(&entry)
+            .with_context(|| format!("Failed to parse {}", entry_path.display()))?;
+
+        let Some((language, index)) = find_language(&entry.languages, language) else {
+            bail!("No language indexes found in {}", entry_path.display());
+        };
+
+        let meta = read_file(&path.join(format!("pagefind.{}.pf_meta", index.hash)))?;
+        let search_index = SearchIndex::new(&meta, get_stemmer(language)).map_err(|e| {
+            anyhow!(
+                "Failed to load the metadata of a bundle written by Pagefind {}: {e}",
+                entry.version
+            )
+        })?;
+
+        Ok(Self {
+            path,
+            language: language.clone(),
+            include_characters: entry.include_characters,
+            index_meta_fields: entry.index_meta_fields,
+            search_index,
+            loaded_chunks: HashSet::new(),
+            loaded_filters: HashSet::new(),
+            excerpt_length: 30,
+        })
+    }
+
+    /// The language of the index being searched.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Set the number of words in each result's excerpt. Defaults to 30.
+    pub fn set_excerpt_length(&mut self, excerpt_length: usize) {
+        self.excerpt_length = excerpt_length;
+    }
+
+    /// Set the ranking weights used for all following searches.
+    pub fn set_ranking(&mut self, ranking_weights: RankingWeights) {
+        self.search_index.set_ranking(ranking_weights);
+    }
+
+    /// Search the bundle, loading any index chunks and result fragments that are needed.
+    ///
+    /// # Arguments
+    /// * `query` - The search query, supporting the same syntax as the JS API. Passing `None` returns all pages that match the filters.
+    /// * `options` - The filters, sorting, and pagination to apply.
+    ///
+    /// # Returns
+    /// The requested page of results, alongside the counts of all results, if successful, otherwise an Error.
+    pub fn search(
+        &mut self,
+        query: Option<&str>,
+        options: &BundleSearchOptions,
+    ) -> Result<SearchResults> {
+        let filter_only = query.is_none();
+        let NormalizedQuery {
+            query,
+            exact,
+            phrase_slop,
+        } = NormalizedQuery::new(
+            query.unwrap_or_default(),
+            &self.include_characters,
+            &self.index_meta_fields,
+        );
+        if query.is_empty() && !filter_only {
+            return Ok(SearchResults {
+                results: vec![],
+                result_count: 0,
+                unfiltered_result_count: 0,
+                filters: FilterCounts::new(),
+                total_filters: FilterCounts::new(),
+            });
+        }
+
+        let filter = options
+            .filters
+            .as_ref()
+            .map(|filters| filters.to_string())
+            .unwrap_or_else(|| "{}".into());
+        let sort = options
+            .sort
+            .iter()
+            .map(|(sort, direction)| match direction {
+                SortDirection::Asc => format!("{sort}:asc"),
+                SortDirection::Desc => format!("{sort}:desc"),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let max_edit_distance = if exact { 0 } else { options.fuzzy };
+
+        for hash in self
+            .search_index
+            .required_index_chunks(&query, max_edit_distance > 0)
+        {
+            self.load_index_chunk(hash)?;
+        }
+        for hash in self.search_index.required_filter_chunks(&filter) {
+            self.load_filter_chunk(hash)?;
+        }
+
+        let response = self.search_index.search(&SearchRequest {
+            query: &query,
+            filter: &filter,
+            sort: &sort,
+            sort_missing: match options.sort_missing {
+                SortMissing::First => "first",
+                SortMissing::Last => "last",
+                SortMissing::Drop => "drop",
+            },
+            exact,
+            phrase_slop,
+            max_edit_distance,
+            offset: options.offset,
+            limit: options.limit,
+            counts_only: options.counts_only,
+        });
+
+        let results = response
+            .results
+            .into_iter()
+            .map(|result| {
+                let mut data = self.load_fragment(&result.page)?;
+                let weighted_locations: Vec<(u32, f32)> = result
+                    .word_locations
+                    .iter()
+                    .map(|word| (word.word_location, word.balanced_score))
+                    .collect();
+                let words: Vec<u32> = weighted_locations
+                    .iter()
+                    .map(|(location, _)| *location)
+                    .collect();
+
+                let content = data.content.replace('<', "&lt;").replace('>', "&gt;");
+                let excerpt_start =
+                    excerpt::calculate_excerpt_region(&weighted_locations, self.excerpt_length);
+                let excerpt =
+                    excerpt::build_excerpt(&content, excerpt_start, self.excerpt_length, &words);
+
+                data.content = data.content.replace('\u{200B}', "");
+
+                Ok(SearchResult {
+                    id: result.page,
+                    score: result.page_score,
+                    words,
+                    excerpt,
+                    data,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(SearchResults {
+            results,
+            result_count: response.filtered_total,
+            unfiltered_result_count: response.unfiltered_total,
+            filters: response.filtered_counts,
+            total_filters: response.total_counts,
+        })
+    }
+
+    /// List every filter in the bundle, with the number of pages under each value.
+    pub fn filters(&mut self) -> Result<FilterCounts> {
+        for hash in self.search_index.all_filter_chunks() {
+            self.load_filter_chunk(hash)?;
+        }
+        Ok(self.search_index.filter_counts(None))
+    }
+
+    /// List every filter in the bundle, with the values of filters indexed with a `filter_separator`
+    /// nested beneath their parent value. The count of each value includes the values nested beneath it.
+    pub fn nested_filters(&mut self) -> Result<NestedFilterCounts> {
+        for hash in self.search_index.all_filter_chunks() {
+            self.load_filter_chunk(hash)?;
+        }
+        Ok(self.search_index.nested_filter_counts())
+    }
+
+    fn load_index_chunk(&mut self, hash: String) -> Result<()> {
+        if self.loaded_chunks.contains(&hash) {
+            return Ok(());
+        }
+        let chunk = read_file(&self.path.join(format!("index/{hash}.pf_index")))?;
+        self.search_index
+            .decode_index_chunk(&chunk)
+            .map_err(|e| anyhow!("Failed to load the index chunk {hash}: {e}"))?;
+        self.loaded_chunks.insert(hash);
+        Ok(())
+    }
+
+    fn load_filter_chunk(&mut self, hash: String) -> Result<()> {
+        if self.loaded_filters.contains(&hash) {
+            return Ok(());
+        }
+        let chunk = read_file(&self.path.join(format!("filter/{hash}.pf_filter")))?;
+        self.search_index
+            .decode_filter_index_chunk(&chunk)
+            .map_err(|e| anyhow!("Failed to load the filter chunk {hash}: {e}"))?;
+        self.loaded_filters.insert(hash);
+        Ok(())
+    }
+
+    fn load_fragment(&self, hash: &str) -> Result<PageFragmentData> {
+        let fragment = read_file(&self.path.join(format!("fragment/{hash}.pf_fragment")))?;
+        serde_json::from_slice(&fragment)
+            .with_context(|| format!("Failed to parse the fragment {hash}"))
+    }
+}
+
+/// Picks the index for a language, falling back to the base language
+/// and then to the language with the most pages.
+fn find_language<'a>(
+    languages: &'a BTreeMap<String, BundleEntryLanguage>,
+    language: &str,
+) -> Option<(&'a String, &'a BundleEntryLanguage)> {
+    let base_language = language.split('-').next().unwrap_or_default();
+    languages
+        .get_key_value(language)
+        .or_else(|| languages.get_key_value(base_language))
+        .or_else(|| languages.iter().max_by_key(|(_, index)| index.page_count))
+}
+
+/// Reads a file written by Pagefind, decompressing it if needed and checking its signature.
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let contents = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut decompressed = if contents.starts_with(DECOMPRESSED_SIGNATURE) {
+        contents
+    } else {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(contents.as_slice())
+            .read_to_end(&mut decompressed)
+            .with_context(|| format!("Failed to decompress {}", path.display()))?;
+        decompressed
+    };
+
+    if !decompressed.starts_with(DECOMPRESSED_SIGNATURE) {
+        bail!(
+            "Decompressing {} succeeded, but it doesn't look like a Pagefind file",
+            path.display()
+        );
+    }
+    decompressed.drain(..DECOMPRESSED_SIGNATURE.len());
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::PagefindIndex;
+    use crate::options::PagefindServiceConfig;
+
+    #[test]
+    fn normalizing_queries() {
+        let fields = vec!["title".to_string()];
+        let normalize = |query: &str| NormalizedQuery::new(query, &['#'], &fields);
+
+        assert_eq!(normalize("Hello, World!").query, "hello world");
+        assert_eq!(normalize("C# -rust  OR Go").query, "c# -rust OR go");
+        assert_eq!(
+            normalize("(cats OR dogs) NOT fish").query,
+            "(cats OR dogs) NOT fish"
+        );
+        assert_eq!(
+            normalize("title:Pagefind author:Liam").query,
+            "title:pagefind authorliam"
+        );
+        assert_eq!(normalize("-(title:Search").query, "-(title:search");
+        assert_eq!(normalize("well-known").query, "wellknown");
+        assert_eq!(normalize("«Ça va?»").query, "ça va");
+        assert_eq!(normalize("snake_case $5 a+b").query, "snake_case $5 a+b");
+
+        assert_eq!(
+            normalize(" \"Hello (World)\"~2 "),
+            NormalizedQuery {
+                query: "hello world".into(),
+                exact: true,
+                phrase_slop: 2,
+            }
+        );
+        assert_eq!(
+            normalize("\"happy post\" OR \"like dogs\""),
+            NormalizedQuery {
+                query: "\"happy post\" OR \"like dogs\"".into(),
+                exact: false,
+                phrase_slop: 0,
+            }
+        );
+        assert!(!normalize("\"\"").exact);
+    }
+
+    #[test]
+    fn parsing_sorts() {
+        assert_eq!(
+            parse_sorts("priority desc, date,relevance").unwrap(),
+            vec![
+                ("priority".to_string(), SortDirection::Desc),
+                ("date".to_string(), SortDirection::Asc),
+                ("relevance".to_string(), SortDirection::Desc),
+            ]
+        );
+        assert!(parse_sorts("").unwrap().is_empty());
+        assert!(parse_sorts("date up").is_err());
+    }
+
+    async fn write_bundle(index: &mut PagefindIndex, name: &str) -> PathBuf {
+        let outdir =
+            std::env::temp_dir().join(format!("pagefind-bundle-{name}-{}", std::process::id()));
+        for file in index.get_files().await.unwrap() {
+            let path = outdir.join(file.filename);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file.contents).unwrap();
+        }
+        outdir
+    }
+
+    #[tokio::test]
+    async fn searching_a_bundle() {
+        let options = PagefindServiceConfig::builder()
+            .force_language("en".to_string())
+            .build();
+        let mut index = PagefindIndex::new(Some(options)).unwrap();
+        for (url, content) in [
+            ("/cat/", "<h1>Cat</h1><p>The cat sat on the mat.</p>"),
+            ("/dog/", "<h1>Dog</h1><p>The dog ran past the cat.</p>"),
+            ("/fish/", "<h1>Fish</h1><p>The fish swam.</p>"),
+        ] {
+            index
+                .add_html_file(
+                    None,
+                    Some(url.into()),
+                    format!("<html><body>{content}</body></html>"),
+                )
+                .await
+                .unwrap();
+        }
+
+        let outdir = write_bundle(&mut index, "search").await;
+        let mut bundle = Bundle::open(&outdir).expect("Bundle should open");
+        assert_eq!(bundle.language(), "en");
+
+        let results = bundle
+            .search(Some("Cats!"), &BundleSearchOptions::default())
+            .expect("Search should succeed");
+        assert_eq!(results.result_count, 2);
+        assert_eq!(results.results[0].data.url, "/cat/");
+        assert!(!results.results[0].words.is_empty());
+
+        let results = bundle
+            .search(
+                Some("the"),
+                &BundleSearchOptions {
+                    offset: 1,
+                    limit: Some(1),
+                    ..Default::default()
+                },
+            )
+            .expect("Search should succeed");
+        assert_eq!(results.result_count, 3);
+        assert_eq!(results.results.len(), 1);
+
+        let results = bundle
+            .search(Some("!!!"), &BundleSearchOptions::default())
+            .expect("Search should succeed");
+        assert_eq!(results.result_count, 0);
+
+        fs::remove_dir_all(outdir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn listing_bundle_filters() {
+        let options = PagefindServiceConfig::builder()
+            .force_language("en".to_string())
+            .filter_separator(" > ".to_string())
+            .build();
+        let mut index = PagefindIndex::new(Some(options)).unwrap();
+        for (url, category) in [
+            ("/aws/", "Guides > Deployment > AWS"),
+            ("/netlify/", "Guides > Deployment > Netlify"),
+            ("/releases/", "Blog"),
+        ] {
+            let content = format!(
+                "<h1>Page</h1><p data-pagefind-filter=\"category\">{category}</p><p data-pagefind-filter=\"kind\">Page</p>"
+            );
+            index
+                .add_html_file(
+                    None,
+                    Some(url.into()),
+                    format!("<html><body>{content}</body></html>"),
+                )
+                .await
+                .unwrap();
+        }
+
+        let outdir = write_bundle(&mut index, "filters").await;
+        let mut bundle = Bundle::open(&outdir).expect("Bundle should open");
+
+        let filters = bundle.filters().expect("Filters should load");
+        let categories: Vec<(&str, usize)> = filters["category"]
+            .iter()
+            .map(|(value, count)| (value.as_str(), *count))
+            .collect();
+        assert_eq!(
+            categories,
+            vec![
+                ("Blog", 1),
+                ("Guides", 2),
+                ("Guides > Deployment", 2),
+                ("Guides > Deployment > AWS", 1),
+                ("Guides > Deployment > Netlify", 1),
+            ]
+        );
+
+        let nested = bundle.nested_filters().expect("Filters should load");
+        let leaf = |count| NestedFilterCount {
+            count,
+            children: Default::default(),
+        };
+        assert_eq!(nested["kind"], [("Page".to_string(), leaf(3))].into());
+        assert_eq!(nested["category"]["Blog"], leaf(1));
+        let deployment = &nested["category"]["Guides"].children["Guides > Deployment"];
+        assert_eq!(nested["category"]["Guides"].count, 2);
+        assert_eq!(deployment.count, 2);
+        assert_eq!(
+            deployment.children,
+            [
+                ("Guides > Deployment > AWS".to_string(), leaf(1)),
+                ("Guides > Deployment > Netlify".to_string(), leaf(1)),
+            ]
+            .into()
+        );
+
+        fs::remove_dir_all(outdir).unwrap();
+    }
+}
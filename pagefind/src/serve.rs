@@ -1,10 +1,18 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use actix_files::{self as fs};
 use actix_web::{http::header::ContentType, web, App, HttpResponse, HttpServer, Responder};
+use anyhow::{anyhow, Context, Result};
 use portpicker::{is_free_tcp, pick_unused_port};
+use serde::Deserialize;
+use serde_json::json;
 
 use crate::playground::{PLAYGROUND_CSS, PLAYGROUND_HTML, PLAYGROUND_JS};
+use crate::search::{parse_sorts, Bundle, BundleSearchOptions, SearchResults};
 
 async fn pg_index() -> impl Responder {
     HttpResponse::Ok()
@@ -24,7 +32,104 @@ async fn pg_css() -> impl Responder {
         .body(PLAYGROUND_CSS)
 }
 
-pub async fn serve_dir(dir: PathBuf) {
+/// The query parameters of the search endpoint, mirroring the options of the JS API
+#[derive(Deserialize)]
+struct SearchApiQuery {
+    q: Option<String>,
+    /// Filters as a JSON object, e.g. `{"category": "Docs"}`
+    filters: Option<String>,
+    /// Sorts written as a string, e.g. `date desc, relevance`
+    sort: Option<String>,
+    sort_missing: Option<String>,
+    fuzzy: Option<u8>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    language: Option<String>,
+}
+
+/// Searches the bundle that was just written, holding one open bundle per requested language
+struct SearchApi {
+    bundle: PathBuf,
+    open_bundles: Mutex<HashMap<String, Arc<Mutex<Bundle>>>>,
+}
+
+impl SearchApiQuery {
+    /// Reads the search options from the query parameters
+    fn search_options(&self) -> Result<BundleSearchOptions> {
+        let filters = self
+            .filters
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .context("The filters parameter should be a JSON object")?;
+        Ok(BundleSearchOptions {
+            filters,
+            sort: parse_sorts(self.sort.as_deref().unwrap_or_default())?,
+            sort_missing: self.sort_missing.as_deref().unwrap_or("last").parse()?,
+            fuzzy: self.fuzzy.unwrap_or_default(),
+            offset: self.offset.unwrap_or_default(),
+            limit: self.limit,
+            counts_only: false,
+        })
+    }
+}
+
+impl SearchApi {
+    fn bundle(&self, language: &str) -> Result<Arc<Mutex<Bundle>>> {
+        let open_bundle = self
+            .open_bundles
+            .lock()
+            .map_err(|_| anyhow!("The search bundle is unavailable"))?
+            .get(language)
+            .cloned();
+        if let Some(bundle) = open_bundle {
+            return Ok(bundle);
+        }
+
+        // Open the bundle without holding the lock, so searches of other languages aren't blocked
+        let bundle = Arc::new(Mutex::new(Bundle::open_language(&self.bundle, language)?));
+        Ok(self
+            .open_bundles
+            .lock()
+            .map_err(|_| anyhow!("The search bundle is unavailable"))?
+            .entry(language.to_string())
+            .or_insert(bundle)
+            .clone())
+    }
+
+    fn search(
+        &self,
+        query: &SearchApiQuery,
+        options: &BundleSearchOptions,
+    ) -> Result<SearchResults> {
+        let bundle = self.bundle(query.language.as_deref().unwrap_or_default())?;
+        let mut bundle = bundle
+            .lock()
+            .map_err(|_| anyhow!("The search bundle is unavailable"))?;
+        bundle.search(query.q.as_deref(), options)
+    }
+}
+
+async fn search_api_handler(
+    api: web::Data<SearchApi>,
+    query: web::Query<SearchApiQuery>,
+) -> impl Responder {
+    let api = api.into_inner();
+    let query = query.into_inner();
+    let options = match query.search_options() {
+        Ok(options) => options,
+        Err(e) => return HttpResponse::BadRequest().json(json!({ "error": format!("{e:#}") })),
+    };
+    match web::block(move || api.search(&query, &options)).await {
+        Ok(Ok(results)) => HttpResponse::Ok().json(results),
+        Ok(Err(e)) => {
+            HttpResponse::InternalServerError().json(json!({ "error": format!("{e:#}") }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({ "error": e.to_string() })),
+    }
+}
+
+pub async fn serve_dir(dir: PathBuf, search_bundle: Option<PathBuf>) {
     let port = if is_free_tcp(1414) {
         1414
     } else {
@@ -41,18 +146,33 @@ pub async fn serve_dir(dir: PathBuf) {
         .unwrap_or(&dir);
 
     println!("\nServing the Pagefind Playground at http://localhost:{port}/pagefind/playground/");
+    if search_bundle.is_some() {
+        println!("Serving the Pagefind search API at http://localhost:{port}/pagefind/api/search");
+    }
     println!("Serving {rel_dir:?} at http://localhost:{port}");
 
+    let search_api = search_bundle.map(|bundle| {
+        web::Data::new(SearchApi {
+            bundle,
+            open_bundles: Mutex::new(HashMap::new()),
+        })
+    });
+
     match HttpServer::new(move || {
-        App::new()
-            .service(
-                web::scope("/pagefind/playground")
-                    .route("/", web::get().to(pg_index))
-                    .route("/index.html", web::get().to(pg_index))
-                    .route("/pagefind-playground.js", web::get().to(pg_js))
-                    .route("/pagefind-playground.css", web::get().to(pg_css)),
-            )
-            .service(fs::Files::new("/", &dir).index_file("index.html"))
+        let mut app = App::new();
+        if let Some(search_api) = &search_api {
+            app = app
+                .app_data(search_api.clone())
+                .route("/pagefind/api/search", web::get().to(search_api_handler));
+        }
+        app.service(
+            web::scope("/pagefind/playground")
+                .route("/", web::get().to(pg_index))
+                .route("/index.html", web::get().to(pg_index))
+                .route("/pagefind-playground.js", web::get().to(pg_js))
+                .route("/pagefind-playground.css", web::get().to(pg_css)),
+        )
+        .service(fs::Files::new("/", &dir).index_file("index.html"))
     })
     .bind(("127.0.0.1", port))
     {
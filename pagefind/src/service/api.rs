@@ -39,7 +39,7 @@ use rust_patch::Patch;
 use std::{collections::BTreeMap, path::PathBuf};
 
 use crate::{
-    fossick::{parser::DomParserResult, Fossicker},
+    fossick::{records::CustomRecord, Fossicker},
     options::PagefindServiceConfig,
     PagefindInboundConfig, SearchOptions, SearchState,
 };
@@ -129,24 +129,15 @@ impl PagefindIndex {
         filters: Option<BTreeMap<String, Vec<String>>>,
         sort: Option<BTreeMap<String, String>>,
     ) -> Result<IndexedFileResponse> {
-        let data = DomParserResult {
-            digest: content,
+        let record = CustomRecord {
+            url,
+            content,
+            language: Some(language),
+            meta: meta.unwrap_or_default(),
             filters: filters.unwrap_or_default(),
             sort: sort.unwrap_or_default(),
-            meta: meta.unwrap_or_default(),
-            anchor_content: BTreeMap::new(),
-            has_custom_body: false,
-            force_inclusion: true,
-            has_html_element: true,
-            has_old_bundle_reference: false,
-            language: self
-                .search_index
-                .options
-                .force_language
-                .clone()
-                .unwrap_or(language),
         };
-        let file = Fossicker::new_with_data(url, data);
+        let file = record.into_fossicker(&self.search_index.options);
         let data = self.search_index.fossick_one(file).await?;
 
         Ok(IndexedFileResponse {
@@ -167,11 +158,11 @@ impl PagefindIndex {
     pub async fn add_directory(&mut self, path: String, glob: Option<String>) -> Result<usize> {
         let defaults: PagefindInboundConfig =
             serde_json::from_str("{}").expect("All fields have serde defaults");
-        let glob = glob.unwrap_or(defaults.glob);
+        let globs = glob.map(|glob| vec![glob]).unwrap_or(defaults.glob.0);
 
         let page_count = self
             .search_index
-            .fossick_many(PathBuf::from(path), glob)
+            .fossick_many(PathBuf::from(path), globs)
             .await?;
 
         Ok(page_count)
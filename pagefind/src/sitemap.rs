@@ -0,0 +1,324 @@
+//! Finds the pages to index from a site's sitemap, rather than globbing the site directory.
+
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use lazy_static::lazy_static;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+
+use crate::utils::xml_element_text;
+
+lazy_static! {
+    static ref URL_ENTRY: Regex = Regex::new(r"(?s)<url\b[^>]*>(.*?)</url>").unwrap();
+    static ref SITEMAP_ENTRY: Regex = Regex::new(r"(?s)<sitemap\b[^>]*>(.*?)</sitemap>").unwrap();
+    static ref SITEMAP_INDEX: Regex = Regex::new(r"<sitemapindex\b").unwrap();
+    static ref LOC: Regex = Regex::new(r"(?s)<loc\b[^>]*>(.*?)</loc>").unwrap();
+    static ref LASTMOD: Regex = Regex::new(r"(?s)<lastmod\b[^>]*>(.*?)</lastmod>").unwrap();
+    static ref PRIORITY: Regex = Regex::new(r"(?s)<priority\b[^>]*>(.*?)</priority>").unwrap();
+}
+
+/// Sitemap indexes can list other sitemap indexes, but only this deep
+const MAX_SITEMAP_DEPTH: usize = 8;
+
+/// A page listed in a sitemap, found on disk
+#[derive(Debug, PartialEq)]
+pub struct SitemapPage {
+    pub file_path: PathBuf,
+    pub lastmod: Option<String>,
+    pub priority: Option<f32>,
+}
+
+#[derive(Debug, Default)]
+pub struct SitemapPages {
+    pub pages: Vec<SitemapPage>,
+    /// Locations listed in the sitemap that don't match a file in the site directory
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct SitemapEntry {
+    loc: String,
+    lastmod: Option<String>,
+    priority: Option<f32>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Sitemap {
+    UrlSet(Vec<SitemapEntry>),
+    Index(Vec<String>),
+}
+
+/// Reads a sitemap, following any sitemap indexes, and maps each listed URL
+/// back to the HTML file it was built from in the site directory.
+pub fn read_sitemap(site_source: &Path, sitemap: &Path) -> Result<SitemapPages> {
+    let mut found = SitemapPages::default();
+    let mut seen_sitemaps = HashSet::new();
+    let mut seen_pages = HashSet::new();
+    read_sitemap_file(
+        site_source,
+        sitemap,
+        0,
+        &mut seen_sitemaps,
+        &mut seen_pages,
+        &mut found,
+    )?;
+    Ok(found)
+}
+
+fn read_sitemap_file(
+    site_source: &Path,
+    sitemap: &Path,
+    depth: usize,
+    seen_sitemaps: &mut HashSet<PathBuf>,
+    seen_pages: &mut HashSet<PathBuf>,
+    found: &mut SitemapPages,
+) -> Result<()> {
+    if depth > MAX_SITEMAP_DEPTH {
+        bail!(
+            "Sitemap indexes are nested more than {MAX_SITEMAP_DEPTH} levels deep at {}",
+            sitemap.to_string_lossy()
+        );
+    }
+    if !seen_sitemaps.insert(sitemap.to_path_buf()) {
+        return Ok(());
+    }
+
+    let bytes = std::fs::read(sitemap)
+        .with_context(|| format!("Failed to read sitemap {}", sitemap.to_string_lossy()))?;
+    let contents = if bytes.starts_with(&[0x1F, 0x8B]) {
+        let mut contents = String::new();
+        GzDecoder::new(&bytes[..])
+            .read_to_string(&mut contents)
+            .with_context(|| {
+                format!("Failed to decompress sitemap {}", sitemap.to_string_lossy())
+            })?;
+        contents
+    } else {
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+
+    match parse_sitemap(&contents) {
+        Sitemap::Index(sitemaps) => {
+            for loc in sitemaps {
+                match resolve_file(site_source, &loc, false) {
+                    Some(child) => read_sitemap_file(
+                        site_source,
+                        &child,
+                        depth + 1,
+                        seen_sitemaps,
+                        seen_pages,
+                        found,
+                    )?,
+                    None => found.missing.push(loc),
+                }
+            }
+        }
+        Sitemap::UrlSet(entries) => {
+            for entry in entries {
+                match resolve_file(site_source, &entry.loc, true) {
+                    Some(file_path) => {
+                        if seen_pages.insert(file_path.clone()) {
+                            found.pages.push(SitemapPage {
+                                file_path,
+                                lastmod: entry.lastmod,
+                                priority: entry.priority,
+                            });
+                        }
+                    }
+                    None => found.missing.push(entry.loc),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_sitemap(xml: &str) -> Sitemap {
+    if SITEMAP_INDEX.is_match(xml) {
+        return Sitemap::Index(
+            SITEMAP_ENTRY
+                .captures_iter(xml)
+                .filter_map(|entry| xml_element_text(&LOC, &entry[1]))
+                .collect(),
+        );
+    }
+
+    Sitemap::UrlSet(
+        URL_ENTRY
+            .captures_iter(xml)
+            .filter_map(|entry| {
+                let entry = &entry[1];
+                Some(SitemapEntry {
+                    loc: xml_element_text(&LOC, entry)?,
+                    lastmod: xml_element_text(&LASTMOD, entry),
+                    priority: xml_element_text(&PRIORITY, entry)
+                        .and_then(|priority| priority.parse::<f32>().ok())
+                        .filter(|priority| priority.is_finite())
+                        .map(|priority| priority.clamp(0.0, 1.0)),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Returns the decoded path of a sitemap location, dropping the origin, query, and fragment
+fn url_path(loc: &str) -> String {
+    let path = match loc.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|i| &rest[i..]).unwrap_or("/"),
+        None => loc,
+    };
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    percent_decode_str(path).decode_utf8_lossy().into_owned()
+}
+
+/// Finds the file in the site directory for a sitemap location.
+/// Pages can also be found at `{path}/index.html` or `{path}.html`.
+fn resolve_file(site_source: &Path, loc: &str, is_page: bool) -> Option<PathBuf> {
+    let path = url_path(loc);
+    let relative = path.trim_start_matches('/');
+    if relative.split('/').any(|segment| segment == "..") {
+        return None;
+    }
+
+    let file = site_source.join(relative);
+    let candidates = if !is_page {
+        vec![file]
+    } else if relative.is_empty() || relative.ends_with('/') {
+        vec![file.join("index.html")]
+    } else {
+        let mut html_file = file.clone().into_os_string();
+        html_file.push(".html");
+        vec![file.clone(), file.join("index.html"), html_file.into()]
+    };
+
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_url_sets() {
+        let sitemap = parse_sitemap(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+                <url>
+                    <loc>https://example.com/</loc>
+                    <lastmod>2024-01-02</lastmod>
+                    <priority>1.0</priority>
+                </url>
+                <url>
+                    <loc> https://example.com/search?q=a&amp;b=c </loc>
+                    <image:image><image:loc>https://example.com/cat.png</image:loc></image:image>
+                    <priority>high</priority>
+                </url>
+                <url><loc><![CDATA[https://example.com/a&b/]]></loc></url>
+                <url><lastmod>2024-01-02</lastmod></url>
+            </urlset>"#,
+        );
+
+        assert_eq!(
+            sitemap,
+            Sitemap::UrlSet(vec![
+                SitemapEntry {
+                    loc: "https://example.com/".into(),
+                    lastmod: Some("2024-01-02".into()),
+                    priority: Some(1.0),
+                },
+                SitemapEntry {
+                    loc: "https://example.com/search?q=a&b=c".into(),
+                    lastmod: None,
+                    priority: None,
+                },
+                SitemapEntry {
+                    loc: "https://example.com/a&b/".into(),
+                    lastmod: None,
+                    priority: None,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parsing_sitemap_indexes() {
+        let sitemap = parse_sitemap(
+            r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <sitemap><loc>https://example.com/sitemap-0.xml</loc></sitemap>
+                <sitemap><loc>/blog/sitemap.xml.gz</loc><lastmod>2024-01-02</lastmod></sitemap>
+            </sitemapindex>"#,
+        );
+
+        assert_eq!(
+            sitemap,
+            Sitemap::Index(vec![
+                "https://example.com/sitemap-0.xml".into(),
+                "/blog/sitemap.xml.gz".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn reading_sitemaps() {
+        let site = std::env::temp_dir().join(format!("pagefind-sitemap-{}", std::process::id()));
+        std::fs::create_dir_all(site.join("docs")).unwrap();
+        for file in ["index.html", "docs/index.html", "about.html"] {
+            std::fs::write(site.join(file), "<html></html>").unwrap();
+        }
+        std::fs::write(
+            site.join("sitemap-index.xml"),
+            "<sitemapindex><sitemap><loc>https://example.com/sitemap-0.xml</loc></sitemap>\
+            <sitemap><loc>https://example.com/sitemap-index.xml</loc></sitemap></sitemapindex>",
+        )
+        .unwrap();
+        std::fs::write(
+            site.join("sitemap-0.xml"),
+            "<urlset><url><loc>https://example.com/</loc><priority>0.8</priority></url>\
+            <url><loc>https://example.com/docs/</loc><lastmod>2024-01-02</lastmod></url>\
+            <url><loc>https://example.com/about</loc></url>\
+            <url><loc>https://example.com/index.html</loc></url>\
+            <url><loc>https://example.com/missing/</loc></url></urlset>",
+        )
+        .unwrap();
+
+        let found = read_sitemap(&site, &site.join("sitemap-index.xml")).unwrap();
+        std::fs::remove_dir_all(&site).unwrap();
+
+        assert_eq!(
+            found.pages,
+            vec![
+                SitemapPage {
+                    file_path: site.join("index.html"),
+                    lastmod: None,
+                    priority: Some(0.8),
+                },
+                SitemapPage {
+                    file_path: site.join("docs/index.html"),
+                    lastmod: Some("2024-01-02".into()),
+                    priority: None,
+                },
+                SitemapPage {
+                    file_path: site.join("about.html"),
+                    lastmod: None,
+                    priority: None,
+                },
+            ]
+        );
+        assert_eq!(found.missing, vec!["https://example.com/missing/"]);
+    }
+
+    #[test]
+    fn sitemap_url_paths() {
+        assert_eq!(url_path("https://example.com"), "/");
+        assert_eq!(url_path("https://example.com/docs/"), "/docs/");
+        assert_eq!(url_path("http://localhost:3000/a/b.html#top"), "/a/b.html");
+        assert_eq!(url_path("https://example.com/caf%C3%A9/?q=1"), "/café/");
+        assert_eq!(url_path("/relative/"), "/relative/");
+    }
+}
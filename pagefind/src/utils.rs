@@ -1,3 +1,6 @@
+use pagefind_web::days_from_civil;
+pub use pagefind_web::parse_iso_date;
+use regex::Regex;
 use sha1::{Digest, Sha1};
 
 /// Symbols that count as part of a word
@@ -14,3 +17,151 @@ pub fn full_hash(bytes: &[u8]) -> String {
         .collect::<Vec<String>>()
         .join("")
 }
+
+/// Returns the text of the first XML element matched by `element`,
+/// a regex whose first group captures the content of the element
+pub fn xml_element_text(element: &Regex, xml: &str) -> Option<String> {
+    xml_text(element.captures(xml)?.get(1)?.as_str())
+}
+
+/// Unwraps a CDATA section, or otherwise decodes XML entities, returning the text if it isn't empty
+pub fn xml_text(value: &str) -> Option<String> {
+    let value = value.trim();
+    let value = match value
+        .strip_prefix("<![CDATA[")
+        .and_then(|value| value.strip_suffix("]]>"))
+    {
+        Some(value) => value.to_string(),
+        None => html_escape::decode_html_entities(value).into_owned(),
+    };
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Parses a date written in the given format, such as `%B %d, %Y` for "March 3, 2024",
+/// into seconds since the Unix epoch. Supports `%Y`, `%m`, `%d`, `%H`, `%M`, `%S`,
+/// and `%B` or `%b` for English month names. Whitespace in the format matches any whitespace.
+pub fn parse_formatted_date(value: &str, format: &str) -> Option<f64> {
+    fn number(value: &str, max_len: usize) -> Option<(i64, &str)> {
+        let len = value
+            .bytes()
+            .take(max_len)
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        Some((value[..len].parse().ok()?, &value[len..]))
+    }
+
+    fn month_name(value: &str) -> Option<(i64, &str)> {
+        const MONTHS: [&str; 12] = [
+            "january",
+            "february",
+            "march",
+            "april",
+            "may",
+            "june",
+            "july",
+            "august",
+            "september",
+            "october",
+            "november",
+            "december",
+        ];
+        MONTHS.iter().zip(1..).find_map(|(name, month)| {
+            [name, &name[0..3]].into_iter().find_map(|name| {
+                let prefix = value.get(..name.len())?;
+                prefix.eq_ignore_ascii_case(name).then(|| {
+                    let rest = &value[name.len()..];
+                    (month, rest.strip_prefix('.').unwrap_or(rest))
+                })
+            })
+        })
+    }
+
+    let mut value = value.trim();
+    let (mut year, mut month, mut day) = (None, None, None);
+    let (mut hours, mut minutes, mut secs) = (0, 0, 0);
+    let mut format = format.trim().chars();
+    while let Some(c) = format.next() {
+        value = match c {
+            '%' => {
+                let specifier = format.next()?;
+                let (n, rest) = match specifier {
+                    'Y' => number(value, 4)?,
+                    'm' | 'd' | 'H' | 'M' | 'S' => number(value, 2)?,
+                    'B' | 'b' => month_name(value)?,
+                    '%' => (0, value.strip_prefix('%')?),
+                    _ => return None,
+                };
+                match specifier {
+                    'Y' => year = Some(n),
+                    'm' | 'B' | 'b' => month = Some(n),
+                    'd' => day = Some(n),
+                    'H' => hours = n,
+                    'M' => minutes = n,
+                    'S' => secs = n,
+                    _ => {}
+                }
+                rest
+            }
+            c if c.is_whitespace() => value.trim_start(),
+            c => value.strip_prefix(c)?,
+        };
+    }
+    if !value.is_empty() || hours > 23 || minutes > 59 || secs > 59 {
+        return None;
+    }
+
+    let days = days_from_civil(year?, month?, day?)?;
+    Some((days * 86400 + (hours * 60 + minutes) * 60 + secs) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_iso_dates() {
+        assert_eq!(parse_iso_date("1970-01-01"), Some(0.0));
+        assert_eq!(parse_iso_date("2024-02-29"), Some(1709164800.0));
+        assert_eq!(parse_iso_date("1969-12-31"), Some(-86400.0));
+        assert_eq!(parse_iso_date("2024-02-29T01:30:00Z"), Some(1709170200.0));
+        assert_eq!(
+            parse_iso_date("2024-02-29T13:30:00+12:00"),
+            Some(1709170200.0)
+        );
+        assert_eq!(parse_iso_date("2024-02-29 01:30"), Some(1709170200.0));
+        assert_eq!(parse_iso_date("2024"), None);
+        assert_eq!(parse_iso_date("2024-13-01"), None);
+        assert_eq!(parse_iso_date("31/01/2024"), None);
+        assert_eq!(parse_iso_date("2024-01-31T25:00"), None);
+        assert_eq!(parse_iso_date("2024-02-31"), None);
+        assert_eq!(parse_iso_date("2023-02-29"), None);
+        assert_eq!(parse_iso_date("2024-04-31"), None);
+        assert_eq!(parse_iso_date("2024-01-01T10:30:€"), None);
+    }
+
+    #[test]
+    fn parsing_formatted_dates() {
+        assert_eq!(
+            parse_formatted_date("February 29, 2024", "%B %d, %Y"),
+            Some(1709164800.0)
+        );
+        assert_eq!(
+            parse_formatted_date("feb. 29,  2024", "%b %d, %Y"),
+            Some(1709164800.0)
+        );
+        assert_eq!(
+            parse_formatted_date("29/02/2024 01:30", "%d/%m/%Y %H:%M"),
+            Some(1709170200.0)
+        );
+        assert_eq!(
+            parse_formatted_date("3/4/2024", "%m/%d/%Y"),
+            parse_iso_date("2024-03-04")
+        );
+        assert_eq!(parse_formatted_date("March 3", "%B %d, %Y"), None);
+        assert_eq!(parse_formatted_date("Marc 3, 2024", "%B %d, %Y"), None);
+        assert_eq!(parse_formatted_date("13/13/2024", "%m/%d/%Y"), None);
+        assert_eq!(parse_formatted_date("February 30, 2024", "%B %d, %Y"), None);
+        assert_eq!(parse_formatted_date("3/4/2024 extra", "%m/%d/%Y"), None);
+    }
+}
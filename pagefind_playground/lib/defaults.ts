@@ -3,4 +3,6 @@ export const pagefindRankingDefaults = {
   pageLength: 0.75,
   termSaturation: 1.4,
   termFrequency: 1.0,
+  termProximity: 0.5,
+  pageWeight: 1.0,
 };
@@ -221,6 +221,21 @@ class Pagefind {
     await this.sleep(Math.floor(Math.random() * 2000));
     return stubbed_filters(2000);
   }
+
+  async nestedFilters() {
+    const filters = await this.filters();
+    return Object.fromEntries(
+      Object.entries(filters).map(([filter, values]) => [
+        filter,
+        Object.fromEntries(
+          Object.entries(values).map(([value, count]) => [
+            value,
+            { count, children: {} },
+          ])
+        ),
+      ])
+    );
+  }
 }
 
 const pagefind = new Pagefind();
@@ -237,3 +252,4 @@ export const debouncedSearch = async (
 ) => await pagefind.debouncedSearch(term, options, debounceTimeoutMs);
 export const preload = async () => {};
 export const filters = async () => await pagefind.filters();
+export const nestedFilters = async () => await pagefind.nestedFilters();
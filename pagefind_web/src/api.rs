@@ -0,0 +1,309 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use minicbor::decode;
+use pagefind_stem::Stemmer;
+
+use crate::query::QueryTerm;
+use crate::search::PageSearchResult;
+use crate::util::*;
+use crate::{IndexChunk, RankingWeights, SearchIndex};
+
+/// The number of pages under each filter value, keyed by filter and then by value
+pub type FilterCounts = BTreeMap<String, BTreeMap<String, usize>>;
+
+/// The number of pages under a filter value, including the pages of every value nested beneath it
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedFilterCount {
+    pub count: usize,
+    /// The values directly beneath this value, keyed by their full value, e.g. `Guides > Deployment`
+    pub children: BTreeMap<String, NestedFilterCount>,
+}
+
+/// The top-level values of each filter, keyed by filter and then by value,
+/// with the values of filters indexed with a `filter_separator` nested beneath their parent
+pub type NestedFilterCounts = BTreeMap<String, BTreeMap<String, NestedFilterCount>>;
+
+/// The options for a single search
+#[derive(Debug, Clone)]
+pub struct SearchRequest<'a> {
+    pub query: &'a str,
+    /// A JSON object of filters, in the same format as the JS API, e.g. `{"color": "red"}`
+    pub filter: &'a str,
+    /// A comma-separated list of sorts and directions, e.g. `date:desc,relevance:desc`
+    pub sort: &'a str,
+    /// Whether results without a sort value are placed `first`, `last`, or are `drop`ped
+    pub sort_missing: &'a str,
+    /// Search for the query as one phrase
+    pub exact: bool,
+    pub phrase_slop: u32,
+    pub max_edit_distance: u8,
+    pub offset: usize,
+    pub limit: Option<usize>,
+    /// Return the counts without any results
+    pub counts_only: bool,
+}
+
+impl Default for SearchRequest<'_> {
+    fn default() -> Self {
+        Self {
+            query: "",
+            filter: "{}",
+            sort: "",
+            sort_missing: "last",
+            exact: false,
+            phrase_slop: 0,
+            max_edit_distance: 0,
+            offset: 0,
+            limit: None,
+            counts_only: false,
+        }
+    }
+}
+
+pub struct SearchResponse {
+    /// The requested page of results, in ranked or sorted order
+    pub results: Vec<PageSearchResult>,
+    /// How many results matched the query and filters, before paginating
+    pub filtered_total: usize,
+    /// How many results matched the query if the filters were removed
+    pub unfiltered_total: usize,
+    pub filtered_counts: FilterCounts,
+    pub total_counts: FilterCounts,
+}
+
+impl SearchIndex {
+    /// Creates a search index from the bytes of a decompressed `pf_meta` file.
+    /// Search terms are stemmed with the given stemmer, which should match the language of the index.
+    pub fn new(metadata_bytes: &[u8], stemmer: Option<Stemmer>) -> Result<Self, decode::Error> {
+        let mut search_index = SearchIndex::empty(stemmer);
+        search_index.decode_metadata(metadata_bytes)?;
+        Ok(search_index)
+    }
+
+    /// Creates a search index with no pages, ready to have metadata decoded into it
+    pub(crate) fn empty(stemmer: Option<Stemmer>) -> Self {
+        SearchIndex {
+            web_version: env!("CARGO_PKG_VERSION"),
+            playground_mode: false,
+            generator_version: None,
+            stemmer,
+            pages: Vec::new(),
+            average_page_length: 0.0,
+            chunks: Vec::new(),
+            filter_chunks: BTreeMap::new(),
+            words: BTreeMap::new(),
+            word_forms: BTreeMap::new(),
+            meta_field_chunks: BTreeMap::new(),
+            meta_field_words: BTreeMap::new(),
+            synonyms: BTreeMap::new(),
+            stop_words: BTreeSet::new(),
+            filters: BTreeMap::new(),
+            filter_orders: BTreeMap::new(),
+            filter_parents: BTreeMap::new(),
+            sorts: BTreeMap::new(),
+            sort_dates: BTreeMap::new(),
+            meta_dates: BTreeMap::new(),
+            ranking_weights: RankingWeights::default(),
+        }
+    }
+
+    pub fn set_ranking(&mut self, ranking_weights: RankingWeights) {
+        self.ranking_weights = ranking_weights;
+    }
+
+    /// Returns the hashes of the index chunks that need to be loaded to search for the query
+    pub fn required_index_chunks(&self, query: &str, fuzzy: bool) -> Vec<String> {
+        let query = self.parse_query(query);
+        let synonym_terms = query
+            .as_ref()
+            .map(|q| self.synonym_terms(q))
+            .unwrap_or_default();
+
+        // Negated terms need their chunks loaded to exclude pages,
+        // but only positive terms are matched loosely and drive any further chunk loading.
+        let mut terms: Vec<QueryTerm> = query.as_ref().map(|q| q.terms()).unwrap_or_default();
+        terms.extend(synonym_terms.iter().map(|term| QueryTerm {
+            term,
+            field: None,
+            negated: false,
+        }));
+        let positive_terms: Vec<QueryTerm> = query
+            .as_ref()
+            .map(|q| q.positive_terms())
+            .unwrap_or_default();
+
+        let mut indexes = self.find_index_chunks(&terms, false);
+        if fuzzy {
+            debug!({ "Fuzzy search enabled. Loading all chunks that may contain near matches." });
+            indexes.extend(self.find_fuzzy_index_chunks(&positive_terms));
+            indexes.sort();
+            indexes.dedup();
+        }
+        if indexes.is_empty() && !positive_terms.is_empty() {
+            debug!({
+                "No index chunks found with strict boundaries. Loading all possible extension chunks."
+            });
+            indexes = self.find_index_chunks(&positive_terms, true);
+        }
+
+        indexes
+    }
+
+    /// Returns the hashes of the filter chunks that need to be loaded to filter by a JSON object of filters
+    pub fn required_filter_chunks(&self, filter: &str) -> Vec<String> {
+        let mut indexes = self.filter_chunks(filter).unwrap_or_default();
+        indexes.sort();
+        indexes.dedup();
+        indexes
+    }
+
+    /// Returns the hashes of every filter chunk, for listing all filters
+    pub fn all_filter_chunks(&self) -> Vec<String> {
+        debug!({ "Finding all filter chunks" });
+
+        let mut indexes: Vec<String> = self.filter_chunks.values().cloned().collect();
+        indexes.sort();
+        indexes.dedup();
+        indexes
+    }
+
+    /// Returns the hashes of the index chunks that may contain completions of the prefix
+    pub fn required_completion_chunks(&self, prefix: &str) -> Vec<String> {
+        debug!({
+            format! {"Finding the index chunks that may complete {:?}", prefix}
+        });
+
+        // Completions can span many chunks, so every chunk that may hold an extension is needed
+        let mut indexes: Vec<String> = self
+            .chunks
+            .iter()
+            .filter(|chunk| !prefix.is_empty() && chunk_may_contain_extensions(chunk, prefix))
+            .map(|chunk| chunk.hash.clone())
+            .collect();
+        indexes.sort();
+        indexes.dedup();
+        indexes
+    }
+
+    /// Searches the loaded chunks, returning the requested page of results alongside the counts
+    /// of all results. The chunks from `required_index_chunks` and `required_filter_chunks`
+    /// need to be loaded first.
+    pub fn search(&self, request: &SearchRequest) -> SearchResponse {
+        let filter_set = self.filter(request.filter);
+        let (unfiltered_results, mut results) = if request.exact {
+            self.exact_term(request.query, filter_set, request.phrase_slop)
+        } else {
+            self.search_term(request.query, filter_set, request.max_edit_distance)
+        };
+        let unfiltered_total = unfiltered_results.len();
+        debug!({ format!("Raw total of {} results", unfiltered_total) });
+
+        let filtered_pages: Vec<usize> = results.iter().map(|r| r.page_index).collect();
+        let filtered_counts = self.filter_counts(Some(&filtered_pages));
+        let total_counts = self.filter_counts(Some(&unfiltered_results));
+
+        self.sort_results(&mut results, request.sort, request.sort_missing);
+        let filtered_total = results.len();
+        debug!({ format!("Filtered total of {} results", filtered_total) });
+
+        // Only the requested page of results is returned, as broad searches
+        // on large sites can otherwise send megabytes of word locations back to JS
+        if request.counts_only {
+            results.clear();
+        } else {
+            if let Some(limit) = request.limit {
+                results.truncate(request.offset.saturating_add(limit));
+            }
+            results.drain(..request.offset.min(results.len()));
+        }
+        debug!({ format!("Returning {} of {} results", results.len(), filtered_total) });
+
+        SearchResponse {
+            results,
+            filtered_total,
+            unfiltered_total,
+            filtered_counts,
+            total_counts,
+        }
+    }
+
+    fn find_index_chunks(&self, terms: &[QueryTerm], load_all_possible: bool) -> Vec<String> {
+        debug!({
+            format! {"Finding the index chunks needed for {:?}", terms}
+        });
+
+        let mut indexes = Vec::new();
+
+        for QueryTerm { term, field, .. } in terms {
+            let term = *term;
+            let term_index = self.term_chunks(*field).iter().find(|chunk| {
+                if load_all_possible {
+                    chunk_may_contain_extensions(chunk, term)
+                } else {
+                    term >= &chunk.from && term <= &chunk.to
+                }
+            });
+            if let Some(index) = term_index {
+                debug!({
+                    format! {"Need {:?} for {:?}", index.hash, term}
+                });
+                indexes.push(index.hash.clone())
+            } else {
+                debug!({
+                    format! {"No hash found for {:?}", term}
+                })
+            }
+        }
+
+        indexes.sort();
+        indexes.dedup();
+
+        indexes
+    }
+
+    /// Fuzzy matches can sit anywhere in the index, so we assume the first character
+    /// of the search term is correct and load every chunk that may contain words starting with it.
+    fn find_fuzzy_index_chunks(&self, terms: &[QueryTerm]) -> Vec<String> {
+        let mut indexes = Vec::new();
+
+        for QueryTerm { term, field, .. } in terms {
+            let Some(first_char) = term.chars().next() else {
+                continue;
+            };
+            let prefix = first_char.to_string();
+
+            for chunk in self.term_chunks(*field) {
+                let chunk_from = chunk.from.get(0..prefix.len()).unwrap_or(&chunk.from);
+                let chunk_to = chunk.to.get(0..prefix.len()).unwrap_or(&chunk.to);
+                if prefix.as_str() >= chunk_from && prefix.as_str() <= chunk_to {
+                    debug!({
+                        format! {"Need {:?} for fuzzy matches of {:?}", chunk.hash, term}
+                    });
+                    indexes.push(chunk.hash.clone());
+                }
+            }
+        }
+
+        indexes
+    }
+}
+
+/// Trims chunk boundaries and the search term to the shortest of either,
+/// to find whether the chunk may contain an extension or prefix of the search term
+fn chunk_may_contain_extensions(chunk: &IndexChunk, term: &str) -> bool {
+    let from_length = term.len().min(chunk.from.len());
+    let to_length = term.len().min(chunk.to.len());
+
+    let (Some(term_pre), Some(chunk_pre)) =
+        (term.get(0..from_length), chunk.from.get(0..from_length))
+    else {
+        return false;
+    };
+
+    let (Some(term_post), Some(chunk_post)) = (term.get(0..to_length), chunk.to.get(0..to_length))
+    else {
+        return false;
+    };
+
+    term_pre >= chunk_pre && term_post <= chunk_post
+}
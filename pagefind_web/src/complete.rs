@@ -0,0 +1,123 @@
+use std::{collections::BTreeSet, ops::Bound};
+
+use crate::SearchIndex;
+
+impl SearchIndex {
+    /// Returns up to `limit` of the loaded words that start with the prefix,
+    /// alongside how many pages contain each word. Words are returned in the form
+    /// they were most often written on the site, rather than their stemmed form,
+    /// and are matched against the prefix in both forms. The words found on the most pages
+    /// come first, and stop words are left out as they make for poor completions.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<(&str, usize)> {
+        if prefix.is_empty() {
+            return vec![];
+        }
+
+        // Stems can be shorter than the prefix, e.g. `kubernet` when completing `kubernete`
+        let shorter_stems = prefix
+            .char_indices()
+            .skip(1)
+            .filter_map(|(i, _)| self.words.get_key_value(&prefix[..i]));
+        let extensions = self
+            .words
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|(word, _)| word.starts_with(prefix));
+
+        let mut completions: Vec<(&str, usize)> = shorter_stems
+            .chain(extensions)
+            .filter(|(word, _)| !self.stop_words.contains(*word))
+            .map(|(word, pages)| {
+                let form = self.word_forms.get(word).unwrap_or(word);
+                (form.as_str(), pages.len())
+            })
+            .filter(|(form, _)| form.starts_with(prefix) && !self.stop_words.contains(*form))
+            .collect();
+
+        completions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        // Different stems can share a form, in which case the form found on the most pages is kept
+        let mut seen = BTreeSet::new();
+        completions.retain(|(form, _)| seen.insert(*form));
+        completions.truncate(limit);
+        completions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PageWord;
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::empty(None);
+        for (word, pages) in [
+            ("kube", 1),
+            ("kubectl", 2),
+            ("kubelet", 2),
+            ("kubernet", 4),
+            ("run", 3),
+            ("runs", 1),
+            ("the", 5),
+            ("theme", 2),
+            ("there", 4),
+        ] {
+            let pages = (0..pages)
+                .map(|page| PageWord {
+                    page,
+                    locs: vec![(0, 0)],
+                })
+                .collect();
+            index.words.insert(word.into(), pages);
+        }
+        for (word, form) in [
+            ("kubernet", "kubernetes"),
+            ("run", "running"),
+            ("runs", "running"),
+        ] {
+            index.word_forms.insert(word.into(), form.into());
+        }
+        index.stop_words.extend(["the".into(), "there".into()]);
+        index
+    }
+
+    #[test]
+    fn completing_prefixes() {
+        let index = index();
+        assert_eq!(
+            index.complete("kube", 10),
+            vec![
+                ("kubernetes", 4),
+                ("kubectl", 2),
+                ("kubelet", 2),
+                ("kube", 1)
+            ]
+        );
+        assert_eq!(index.complete("kubel", 10), vec![("kubelet", 2)]);
+        assert!(index.complete("kubes", 10).is_empty());
+    }
+
+    #[test]
+    fn limiting_completions() {
+        let index = index();
+        assert_eq!(
+            index.complete("kube", 2),
+            vec![("kubernetes", 4), ("kubectl", 2)]
+        );
+        assert_eq!(index.complete("kube", 1), vec![("kubernetes", 4)]);
+        assert!(index.complete("kube", 0).is_empty());
+        assert!(index.complete("", 10).is_empty());
+    }
+
+    #[test]
+    fn completing_word_forms() {
+        let index = index();
+        assert_eq!(index.complete("kubernete", 10), vec![("kubernetes", 4)]);
+        assert!(index.complete("kubernets", 10).is_empty());
+        assert_eq!(index.complete("run", 10), vec![("running", 3)]);
+    }
+
+    #[test]
+    fn skipping_stop_words() {
+        let index = index();
+        assert_eq!(index.complete("the", 10), vec![("theme", 2)]);
+    }
+}
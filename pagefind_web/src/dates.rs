@@ -0,0 +1,141 @@
+/// Parses an ISO 8601 date such as `2024-01-31`, optionally with a time
+/// such as `2024-01-31T09:30:00+13:00`, into seconds since the Unix epoch.
+/// Times without an offset are treated as UTC.
+pub fn parse_iso_date(value: &str) -> Option<f64> {
+    fn digits(value: &str, len: usize) -> Option<i64> {
+        if value.len() != len || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        value.parse().ok()
+    }
+
+    let (date, time) = match value.trim().split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value.trim(), None),
+    };
+
+    let mut date_parts = date.split('-');
+    let year = digits(date_parts.next()?, 4)?;
+    let month = digits(date_parts.next()?, 2)?;
+    let day = digits(date_parts.next()?, 2)?;
+    if date_parts.next().is_some() {
+        return None;
+    }
+
+    let mut seconds = (days_from_civil(year, month, day)? * 86400) as f64;
+    if let Some(time) = time {
+        let (time, offset_minutes) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+            (time, 0)
+        } else if let Some(offset_start) = time.rfind(['+', '-']) {
+            let (time, offset) = time.split_at(offset_start);
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = offset[1..].split_once(':').unwrap_or((&offset[1..], "00"));
+            (time, sign * (digits(hours, 2)? * 60 + digits(minutes, 2)?))
+        } else {
+            (time, 0)
+        };
+
+        let mut time_parts = time.split(':');
+        let hours = digits(time_parts.next()?, 2)?;
+        let minutes = digits(time_parts.next()?, 2)?;
+        let secs = match time_parts.next() {
+            Some(secs) if secs.get(0..2).and_then(|s| digits(s, 2)).is_some() => {
+                secs.parse::<f64>().ok()?
+            }
+            Some(_) => return None,
+            None => 0.0,
+        };
+        if time_parts.next().is_some() || hours > 23 || minutes > 59 {
+            return None;
+        }
+        seconds += ((hours * 60 + minutes - offset_minutes) * 60) as f64 + secs;
+    }
+
+    Some(seconds)
+}
+
+/// Days since the Unix epoch, from Howard Hinnant's days_from_civil algorithm.
+/// Returns `None` for months outside 1–12 or days past the end of the month.
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> Option<i64> {
+    let is_leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return None;
+    }
+
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), Some(0));
+        assert_eq!(days_from_civil(1969, 12, 31), Some(-1));
+        assert_eq!(days_from_civil(2000, 2, 29), Some(11016));
+        assert_eq!(days_from_civil(2024, 1, 31), Some(19753));
+        assert_eq!(days_from_civil(1600, 3, 1), Some(-135080));
+    }
+
+    #[test]
+    fn invalid_days() {
+        assert_eq!(days_from_civil(2023, 2, 29), None);
+        assert_eq!(days_from_civil(1900, 2, 29), None);
+        assert_eq!(days_from_civil(2024, 4, 31), None);
+        assert_eq!(days_from_civil(2024, 13, 1), None);
+        assert_eq!(days_from_civil(2024, 1, 0), None);
+    }
+
+    #[test]
+    fn parsing_dates() {
+        assert_eq!(parse_iso_date("2024-01-31"), Some(1706659200.0));
+        assert_eq!(parse_iso_date(" 2024-01-31 "), Some(1706659200.0));
+        assert_eq!(parse_iso_date("1970-01-01"), Some(0.0));
+    }
+
+    #[test]
+    fn parsing_dates_with_times() {
+        assert_eq!(parse_iso_date("2024-01-31T09:30"), Some(1706693400.0));
+        assert_eq!(parse_iso_date("2024-01-31 09:30:00"), Some(1706693400.0));
+        assert_eq!(parse_iso_date("2024-01-31T09:30:00Z"), Some(1706693400.0));
+        assert_eq!(
+            parse_iso_date("2024-01-31T09:30:00+13:00"),
+            Some(1706646600.0)
+        );
+        assert_eq!(parse_iso_date("2024-01-31T09:30:00+1300"), None);
+        assert_eq!(parse_iso_date("2024-01-31T09:30+13"), Some(1706646600.0));
+        assert_eq!(
+            parse_iso_date("2024-01-31T09:30:15.5-05:30"),
+            Some(1706713215.5)
+        );
+    }
+
+    #[test]
+    fn invalid_dates() {
+        assert_eq!(parse_iso_date(""), None);
+        assert_eq!(parse_iso_date("2024"), None);
+        assert_eq!(parse_iso_date("2024-1-31"), None);
+        assert_eq!(parse_iso_date("24-01-31"), None);
+        assert_eq!(parse_iso_date("2024-01-31-01"), None);
+        assert_eq!(parse_iso_date("2024-02-30"), None);
+        assert_eq!(parse_iso_date("31/01/2024"), None);
+        assert_eq!(parse_iso_date("January 31, 2024"), None);
+        assert_eq!(parse_iso_date("2024-01-31T24:00"), None);
+        assert_eq!(parse_iso_date("2024-01-31T09:60"), None);
+        assert_eq!(parse_iso_date("2024-01-31T09:30:xx"), None);
+        assert_eq!(parse_iso_date("2024-01-31T09:30:00:00"), None);
+    }
+}
@@ -2,8 +2,11 @@ use bit_set::BitSet;
 use pagefind_microjson::JSONValue;
 use pagefind_microjson::JSONValueType;
 
+use crate::dates::parse_iso_date;
 use crate::util::*;
-use crate::SearchIndex;
+use std::collections::BTreeMap;
+
+use crate::{FilterCounts, NestedFilterCount, NestedFilterCounts, SearchIndex};
 
 #[derive(Debug)]
 pub enum FilterBehaviour {
@@ -24,24 +27,88 @@ fn collapse(mut maps: Vec<BitSet>, behaviour: FilterBehaviour) -> BitSet {
     output
 }
 
+/// Reads the bound of a range filter as a number, or as a date in seconds since the epoch
+fn parse_range_bound(bound: &JSONValue) -> Option<f64> {
+    use JSONValueType as J;
+
+    match bound.value_type {
+        J::Number => bound
+            .read_integer()
+            .map(|n| n as f64)
+            .or_else(|_| bound.read_float().map(|n| n as f64))
+            .ok(),
+        J::String => {
+            let bound = bound.read_string().ok()?;
+            parse_iso_date(bound).or_else(|| bound.trim().parse::<f64>().ok())
+        }
+        _ => None,
+    }
+}
+
 impl SearchIndex {
-    // TODO: Move this intersection, and the string formatting, into better methods.
-    pub fn get_filters(&self, obj: &mut write_json::Object, intersect_pages: Option<Vec<usize>>) {
-        let intersect_pages = intersect_pages.as_ref();
-        for (filter, values) in &self.filters {
-            let mut filter_obj = obj.object(filter);
-
-            for (value, pages) in values {
-                let len = match intersect_pages {
-                    Some(intersection) => pages
-                        .iter()
-                        .filter(|p| intersection.contains(&(**p as usize)))
-                        .count(),
-                    None => pages.len(),
-                };
-                filter_obj.number(value, len as f64);
-            }
+    /// Counts the pages under each loaded filter value,
+    /// only counting the given pages if `intersect_pages` is set
+    pub fn filter_counts(&self, intersect_pages: Option<&[usize]>) -> FilterCounts {
+        let intersect_pages: Option<BitSet> =
+            intersect_pages.map(|pages| pages.iter().copied().collect());
+        self.filters
+            .iter()
+            .map(|(filter, values)| {
+                let counts = values
+                    .iter()
+                    .map(|(value, pages)| {
+                        let len = match &intersect_pages {
+                            Some(intersection) => pages
+                                .iter()
+                                .filter(|p| intersection.contains(**p as usize))
+                                .count(),
+                            None => pages.len(),
+                        };
+                        (value.clone(), len)
+                    })
+                    .collect();
+                (filter.clone(), counts)
+            })
+            .collect()
+    }
+
+    /// Counts the pages under each loaded filter value, nesting the values of filters
+    /// indexed with a `filter_separator` beneath their parent value
+    pub fn nested_filter_counts(&self) -> NestedFilterCounts {
+        fn nest(
+            counts: &BTreeMap<String, usize>,
+            parents: Option<&BTreeMap<String, String>>,
+            parent: Option<&str>,
+        ) -> BTreeMap<String, NestedFilterCount> {
+            counts
+                .iter()
+                .filter(|(value, _)| {
+                    parents
+                        .and_then(|parents| parents.get(*value))
+                        .map(String::as_str)
+                        == parent
+                })
+                .map(|(value, count)| {
+                    let children = match parents {
+                        Some(_) => nest(counts, parents, Some(value)),
+                        None => BTreeMap::new(),
+                    };
+                    let count = NestedFilterCount {
+                        count: *count,
+                        children,
+                    };
+                    (value.clone(), count)
+                })
+                .collect()
         }
+
+        self.filter_counts(None)
+            .into_iter()
+            .map(|(filter, counts)| {
+                let values = nest(&counts, self.filter_parents.get(&filter), None);
+                (filter, values)
+            })
+            .collect()
     }
 
     fn invert(&self, set: &mut BitSet) {
@@ -120,6 +187,9 @@ impl SearchIndex {
                             ("all" | "not", J::Object | J::Array | J::String) => Some(
                                 self.parse_filter_value(filter_key, value, FilterBehaviour::All),
                             ),
+                            ("gt" | "gte" | "lt" | "lte", J::Number | J::String) => {
+                                Some(Some(self.filter_range(filter_key, k, value)))
+                            }
                             _ => {
                                 debug!({
                                     format! {"Unsupported filter key {k} value {:?}", value.value_type}
@@ -150,6 +220,45 @@ impl SearchIndex {
         }
     }
 
+    fn filter_range(&self, filter_key: &str, comparison: &str, bound: JSONValue) -> BitSet {
+        debug!({
+            format! {"Filtering {filter_key} by {comparison} {bound:?}"}
+        });
+
+        let mut set = BitSet::new();
+        let Some(ordered_values) = self.filter_orders.get(filter_key) else {
+            debug!({
+                format! {"{filter_key} does not contain numbers or dates"}
+            });
+            return set;
+        };
+        let Some(bound) = parse_range_bound(&bound) else {
+            debug!({
+                format! {"{bound:?} is not a number or date"}
+            });
+            return set;
+        };
+
+        let filter_map = self.filters.get(filter_key);
+        for (order, value) in ordered_values {
+            let in_range = match comparison {
+                "gt" => *order > bound,
+                "gte" => *order >= bound,
+                "lt" => *order < bound,
+                _ => *order <= bound,
+            };
+            if !in_range {
+                continue;
+            }
+            if let Some(pages) = filter_map.and_then(|m| m.get(value)) {
+                for page in pages {
+                    set.insert(*page as usize);
+                }
+            }
+        }
+        set
+    }
+
     fn parse_filter_arr(&self, filter: JSONValue, behaviour: FilterBehaviour) -> Option<BitSet> {
         use JSONValueType as J;
         debug_assert!(matches!(filter.value_type, J::Array));
@@ -18,6 +18,14 @@ use pagefind_microjson::{JSONValue, JSONValueType};
             ]
         },
         ...
+    ],
+    [
+        f64             // position of each filter value on a number line,
+        ...             // or empty if the values aren't all numbers or dates
+    ],
+    [
+        u32             // position of each filter value's parent value plus one,
+        ...             // or zero for top-level values, or empty if nothing is nested
     ]
 }
 */
@@ -37,6 +45,7 @@ impl SearchIndex {
 
         debug!({ format!("Reading {:#?} values", values) });
         let mut value_map = BTreeMap::new();
+        let mut value_order = Vec::with_capacity(values as usize);
         for _ in 0..values {
             consume_fixed_arr!(decoder);
             let value = consume_string!(decoder);
@@ -47,9 +56,64 @@ impl SearchIndex {
                 page_arr.push(consume_num!(decoder));
             }
 
+            value_order.push(value.clone());
             value_map.insert(value, page_arr);
         }
 
+        debug!({ "Reading value orders array" });
+        let orders = consume_arr_len!(decoder);
+        if orders > 0 {
+            debug!({ format!("Reading {:#?} value orders", orders) });
+            // Orders are in the same order as the values, which are already sorted by them
+            let mut ordered_values = Vec::with_capacity(orders as usize);
+            for value in value_order.iter().take(orders as usize) {
+                ordered_values.push((consume_float!(decoder), value.clone()));
+            }
+            self.filter_orders.insert(filter.clone(), ordered_values);
+        }
+
+        debug!({ "Reading value parents array" });
+        let parents = consume_arr_len!(decoder);
+        if parents > 0 {
+            debug!({ format!("Reading {:#?} value parents", parents) });
+            let mut parent_arr = Vec::with_capacity(parents as usize);
+            for _ in 0..parents {
+                parent_arr.push(consume_num!(decoder) as usize);
+            }
+
+            // Filtering to a value includes everything nested beneath it,
+            // so each value takes on the pages of all of its descendants
+            let own_pages: Vec<Vec<u32>> = value_order
+                .iter()
+                .map(|value| value_map.get(value).cloned().unwrap_or_default())
+                .collect();
+            for (i, pages) in own_pages.into_iter().enumerate() {
+                let mut parent = parent_arr.get(i).copied().unwrap_or_default();
+                while let Some(parent_value) =
+                    parent.checked_sub(1).and_then(|p| value_order.get(p))
+                {
+                    if let Some(parent_pages) = value_map.get_mut(parent_value) {
+                        parent_pages.extend_from_slice(&pages);
+                    }
+                    parent = parent_arr.get(parent - 1).copied().unwrap_or_default();
+                }
+            }
+            for pages in value_map.values_mut() {
+                pages.sort_unstable();
+                pages.dedup();
+            }
+
+            let value_parents = value_order
+                .iter()
+                .zip(&parent_arr)
+                .filter_map(|(value, parent)| {
+                    let parent_value = value_order.get(parent.checked_sub(1)?)?;
+                    Some((value.clone(), parent_value.clone()))
+                })
+                .collect();
+            self.filter_parents.insert(filter.clone(), value_parents);
+        }
+
         self.filters.insert(filter, value_map);
 
         debug!({ "Finished reading values" });
@@ -113,3 +177,157 @@ impl SearchIndex {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minicbor::Encoder;
+
+    /// Encodes a filter index chunk of values with their pages, orders, and parents
+    fn filter_chunk(
+        filter: &str,
+        values: &[(&str, &[u32])],
+        orders: &[f64],
+        parents: &[u32],
+    ) -> Vec<u8> {
+        let mut encoder = Encoder::new(Vec::new());
+        encoder.array(4).unwrap().str(filter).unwrap();
+        encoder.array(values.len() as u64).unwrap();
+        for (value, pages) in values {
+            encoder.array(2).unwrap().str(value).unwrap();
+            encoder.array(pages.len() as u64).unwrap();
+            for page in *pages {
+                encoder.u32(*page).unwrap();
+            }
+        }
+        encoder.array(orders.len() as u64).unwrap();
+        for order in orders {
+            encoder.f64(*order).unwrap();
+        }
+        encoder.array(parents.len() as u64).unwrap();
+        for parent in parents {
+            encoder.u32(*parent).unwrap();
+        }
+        encoder.into_writer()
+    }
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::empty(None);
+        for (filter, chunk) in [
+            (
+                "year",
+                filter_chunk(
+                    "year",
+                    &[
+                        ("2019", &[0]),
+                        ("2020", &[1]),
+                        ("2021", &[2, 3]),
+                        ("2022", &[4]),
+                    ],
+                    &[2019.0, 2020.0, 2021.0, 2022.0],
+                    &[],
+                ),
+            ),
+            (
+                "published",
+                filter_chunk(
+                    "published",
+                    &[("2024-01-01", &[0, 1]), ("2024-02-01", &[2])],
+                    &[1704067200.0, 1706745600.0],
+                    &[],
+                ),
+            ),
+            (
+                "tag",
+                filter_chunk("tag", &[("cats", &[0]), ("dogs", &[1])], &[], &[]),
+            ),
+        ] {
+            index.decode_filter_index_chunk(&chunk).unwrap();
+            index.filter_chunks.insert(filter.into(), filter.into());
+        }
+        index
+    }
+
+    fn filter(index: &SearchIndex, filter: &str) -> Vec<usize> {
+        index.filter(filter).unwrap().iter().collect()
+    }
+
+    #[test]
+    fn decoding_value_orders() {
+        let index = index();
+        assert_eq!(
+            index.filter_orders.get("year").unwrap()[1],
+            (2020.0, "2020".to_string())
+        );
+        assert!(!index.filter_orders.contains_key("tag"));
+    }
+
+    #[test]
+    fn filtering_by_range_bounds() {
+        let index = index();
+        assert_eq!(filter(&index, r#"{"year": {"gt": 2020}}"#), vec![2, 3, 4]);
+        assert_eq!(
+            filter(&index, r#"{"year": {"gte": 2020}}"#),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(filter(&index, r#"{"year": {"lt": 2020}}"#), vec![0]);
+        assert_eq!(filter(&index, r#"{"year": {"lte": 2020}}"#), vec![0, 1]);
+        assert_eq!(
+            filter(&index, r#"{"year": {"gte": 2020, "lt": 2022}}"#),
+            vec![1, 2, 3]
+        );
+        assert_eq!(filter(&index, r#"{"year": {"gt": 2020.5}}"#), vec![2, 3, 4]);
+        assert_eq!(filter(&index, r#"{"year": {"lte": "2020"}}"#), vec![0, 1]);
+        assert!(filter(&index, r#"{"year": {"gt": 2022}}"#).is_empty());
+    }
+
+    #[test]
+    fn filtering_by_date_bounds() {
+        let index = index();
+        assert_eq!(
+            filter(&index, r#"{"published": {"gte": "2024-01-15"}}"#),
+            vec![2]
+        );
+        assert_eq!(
+            filter(&index, r#"{"published": {"lt": "2024-02-01T00:00:00Z"}}"#),
+            vec![0, 1]
+        );
+        assert_eq!(
+            filter(&index, r#"{"published": {"gte": "2024-01-01"}}"#),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn filtering_by_invalid_bounds() {
+        let index = index();
+        assert!(filter(&index, r#"{"year": {"gt": "soon"}}"#).is_empty());
+        assert!(filter(&index, r#"{"tag": {"gt": 1}}"#).is_empty());
+    }
+
+    #[test]
+    fn decoding_nested_values() {
+        let mut index = SearchIndex::empty(None);
+        let chunk = filter_chunk(
+            "category",
+            &[
+                ("animals", &[0]),
+                ("animals/cats", &[1]),
+                ("animals/cats/lions", &[2]),
+                ("plants", &[3]),
+            ],
+            &[],
+            &[0, 1, 2, 0],
+        );
+        index.decode_filter_index_chunk(&chunk).unwrap();
+
+        let values = index.filters.get("category").unwrap();
+        assert_eq!(values.get("animals").unwrap(), &vec![0, 1, 2]);
+        assert_eq!(values.get("animals/cats").unwrap(), &vec![1, 2]);
+        assert_eq!(values.get("plants").unwrap(), &vec![3]);
+
+        let parents = index.filter_parents.get("category").unwrap();
+        assert_eq!(parents.get("animals/cats/lions").unwrap(), "animals/cats");
+        assert!(!parents.contains_key("animals"));
+    }
+}
@@ -1,4 +1,4 @@
-use super::{PageWord, SearchIndex};
+use super::{IndexChunk, PageWord, SearchIndex};
 use crate::util::*;
 use minicbor::{decode, Decoder};
 
@@ -17,10 +17,12 @@ use minicbor::{decode, Decoder};
                     ]
                 },
                 ...
-            ]
+            ],
+            String,             // the word before stemming, empty if unchanged
         },
         ...
-    ]
+    ],
+    String,                     // metadata field, empty for page content
 }
 */
 
@@ -29,13 +31,15 @@ impl SearchIndex {
         debug!({ format!("Decoding {:#?} index bytes", index_bytes.len()) });
         let mut decoder = Decoder::new(index_bytes);
 
-        consume_fixed_arr!(decoder);
+        let fields = consume_fixed_arr!(decoder);
 
         debug!({ "Reading words array" });
         let words = consume_arr_len!(decoder);
         debug!({ format!("Reading {:#?} words", words) });
+        let mut chunk_words = Vec::with_capacity(words as usize);
+        let mut chunk_forms = Vec::new();
         for _ in 0..words {
-            consume_fixed_arr!(decoder);
+            let word_fields = consume_fixed_arr!(decoder);
             let key = consume_string!(decoder);
 
             let pages = consume_arr_len!(decoder);
@@ -70,10 +74,42 @@ impl SearchIndex {
                 page_arr.push(page);
             }
 
-            self.words.insert(key, page_arr);
+            if let Some(3) = word_fields {
+                let form = consume_string!(decoder);
+                if !form.is_empty() {
+                    chunk_forms.push((key.clone(), form));
+                }
+            }
+
+            chunk_words.push((key, page_arr));
         }
         debug!({ "Finished reading words" });
 
+        let field = match fields {
+            Some(2) => consume_string!(decoder),
+            _ => String::new(),
+        };
+        let word_map = if field.is_empty() {
+            self.word_forms.extend(chunk_forms);
+            &mut self.words
+        } else {
+            debug!({ format!("Storing words for the {:#?} field", field) });
+            self.meta_field_words.entry(field).or_default()
+        };
+        word_map.extend(chunk_words);
+
         Ok(())
     }
+
+    /// The index chunks holding the words of the given metadata field, or of the page content
+    pub fn term_chunks(&self, field: Option<&str>) -> &[IndexChunk] {
+        match field {
+            Some(field) => self
+                .meta_field_chunks
+                .get(field)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+            None => &self.chunks,
+        }
+    }
 }
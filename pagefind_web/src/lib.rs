@@ -1,19 +1,35 @@
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-use pagefind_microjson::JSONValue;
-use search::{stems_from_term, BM25Params, ScoringMetrics};
+use pagefind_microjson::{JSONValue, JSONValueType};
+use pagefind_stem::Stemmer;
+use query::QueryTerm;
+use recency::PageDates;
+use search::{BM25Params, ScoringMetrics};
 use util::*;
 use wasm_bindgen::prelude::*;
 
-use crate::search::BalancedWordScore;
+pub use api::{FilterCounts, NestedFilterCount, NestedFilterCounts, SearchRequest, SearchResponse};
+pub use dates::{days_from_civil, parse_iso_date};
+pub use normalize::NormalizedQuery;
+pub use recency::{RecencyDecay, RecencyField};
+pub use search::{BalancedWordScore, PageSearchResult};
 
+mod api;
+mod complete;
+mod dates;
 mod filter;
 mod filter_index;
 mod index;
 mod metadata;
+mod normalize;
+mod punctuation;
+mod query;
+mod recency;
 mod search;
+mod sort;
+mod suggest;
 mod util;
 
 pub struct PageWord {
@@ -30,19 +46,31 @@ pub struct IndexChunk {
 pub struct Page {
     hash: String,
     word_count: u32,
+    page_weight: f32,
 }
 
 pub struct SearchIndex {
     web_version: &'static str,
     playground_mode: bool,
     generator_version: Option<String>,
+    stemmer: Option<Stemmer>,
     pages: Vec<Page>,
     average_page_length: f32,
     chunks: Vec<IndexChunk>,
     filter_chunks: BTreeMap<String, String>,
     words: BTreeMap<String, Vec<PageWord>>,
+    /// The form each stemmed word was most often written in, where it differs from the word
+    word_forms: BTreeMap<String, String>,
+    meta_field_chunks: BTreeMap<String, Vec<IndexChunk>>,
+    meta_field_words: BTreeMap<String, BTreeMap<String, Vec<PageWord>>>,
+    synonyms: BTreeMap<String, Vec<String>>,
+    stop_words: BTreeSet<String>,
     filters: BTreeMap<String, BTreeMap<String, Vec<u32>>>,
-    sorts: BTreeMap<String, Vec<u32>>,
+    filter_orders: BTreeMap<String, Vec<(f64, String)>>,
+    filter_parents: BTreeMap<String, BTreeMap<String, String>>,
+    sorts: BTreeMap<String, Vec<Option<u32>>>,
+    sort_dates: BTreeMap<String, PageDates>,
+    meta_dates: BTreeMap<String, PageDates>,
     ranking_weights: RankingWeights,
 }
 
@@ -73,6 +101,29 @@ pub struct RankingWeights {
     /// Numbers between 0.0 and 1.0 will interpolate between the two ranking methods.
     /// Must be clamped to 0..=1
     pub term_frequency: f32,
+    /// Controls how much ranking favours pages where the search terms appear close together.
+    /// At 0.0, the distance between search terms has no effect on the ranking.
+    /// Higher numbers boost pages where the terms appear next to each other,
+    /// e.g. a score of 1.0 doubles the score of a page containing the terms side by side.
+    /// Must be >= 0
+    pub term_proximity: f32,
+    /// Controls how much the page weight set with `data-pagefind-page-weight` affects ranking.
+    /// At 1.0, a page weight of 2.0 doubles the score of that page.
+    /// At 0.0, page weights have no effect on the ranking.
+    /// Must be >= 0
+    pub page_weight: f32,
+    /// The date sort or metadata field used to boost recent pages, if any.
+    pub recency_field: Option<RecencyField>,
+    /// Controls how quickly the recency boost falls away as pages get older than the newest page.
+    pub recency_decay: RecencyDecay,
+    /// The age in days at which a page receives half of the recency boost.
+    /// Must be > 0
+    pub recency_half_life: f32,
+    /// Controls how much recency affects ranking.
+    /// At 0.0, the date of a page has no effect on the ranking.
+    /// At 1.0, the newest page has its score doubled, and older pages receive less of a boost.
+    /// Must be >= 0
+    pub recency_weight: f32,
 }
 
 impl Default for RankingWeights {
@@ -82,6 +133,12 @@ impl Default for RankingWeights {
             page_length: 0.75,
             term_saturation: 1.4,
             term_frequency: 1.0,
+            term_proximity: 0.5,
+            page_weight: 1.0,
+            recency_field: None,
+            recency_decay: RecencyDecay::Exponential,
+            recency_half_life: 365.0,
+            recency_weight: 1.0,
         }
     }
 }
@@ -95,29 +152,20 @@ extern "C" {
 
 #[cfg(debug_assertions)]
 fn debug_log(s: &str) {
+    // The console only exists when running as WebAssembly
+    #[cfg(target_arch = "wasm32")]
     log(&format!("From WASM: {}", s));
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = s;
 }
 
 #[wasm_bindgen]
 pub fn init_pagefind(metadata_bytes: &[u8]) -> *mut SearchIndex {
     #[cfg(debug_assertions)]
     debug_log("Initializing Pagefind");
-    let mut search_index = SearchIndex {
-        web_version: env!("CARGO_PKG_VERSION"),
-        playground_mode: false,
-        generator_version: None,
-        pages: Vec::new(),
-        average_page_length: 0.0,
-        chunks: Vec::new(),
-        filter_chunks: BTreeMap::new(),
-        words: BTreeMap::new(),
-        filters: BTreeMap::new(),
-        sorts: BTreeMap::new(),
-        ranking_weights: RankingWeights::default(),
-    };
 
-    match search_index.decode_metadata(metadata_bytes) {
-        Ok(_) => Box::into_raw(Box::new(search_index)),
+    match SearchIndex::new(metadata_bytes, Stemmer::try_create_default().ok()) {
+        Ok(search_index) => Box::into_raw(Box::new(search_index)),
         #[allow(unused_variables)]
         Err(e) => {
             #[cfg(debug_assertions)]
@@ -176,6 +224,70 @@ pub fn set_ranking_weights(ptr: *mut SearchIndex, weights: &str) -> *mut SearchI
         search_index.ranking_weights.term_frequency = term_frequency.clamp(0.0, 1.0);
     }
 
+    if let Ok(term_proximity) = weights
+        .get_key_value("term_proximity")
+        .and_then(|v| v.read_float())
+    {
+        search_index.ranking_weights.term_proximity = term_proximity.max(0.0);
+    }
+
+    if let Ok(page_weight) = weights
+        .get_key_value("page_weight")
+        .and_then(|v| v.read_float())
+    {
+        search_index.ranking_weights.page_weight = page_weight.max(0.0);
+    }
+
+    // A null or empty string for the recency field turns recency off,
+    // while leaving both keys out keeps the current field.
+    let read_recency_field = |key: &str| -> Option<String> {
+        let value = weights.get_key_value(key).ok()?;
+        match value.value_type {
+            JSONValueType::Null => Some(String::new()),
+            _ => value.read_string().map(str::to_owned).ok(),
+        }
+    };
+    match (
+        read_recency_field("recency_sort"),
+        read_recency_field("recency_meta"),
+    ) {
+        (None, None) => {}
+        (Some(sort), _) if !sort.is_empty() => {
+            search_index.ranking_weights.recency_field = Some(RecencyField::Sort(sort));
+        }
+        (_, Some(field)) if !field.is_empty() => {
+            search_index.ranking_weights.recency_field = Some(RecencyField::Meta(field));
+        }
+        _ => {
+            search_index.ranking_weights.recency_field = None;
+        }
+    }
+
+    if let Some(recency_decay) = weights
+        .get_key_value("recency_decay")
+        .and_then(|v| v.read_string().map(str::to_owned))
+        .ok()
+        .and_then(|d| RecencyDecay::parse(&d))
+    {
+        search_index.ranking_weights.recency_decay = recency_decay;
+    }
+
+    if let Ok(recency_half_life) = weights
+        .get_key_value("recency_half_life")
+        .and_then(|v| v.read_float())
+    {
+        if recency_half_life > 0.0 {
+            search_index.ranking_weights.recency_half_life = recency_half_life;
+        }
+    }
+
+    if let Ok(recency_weight) = weights
+        .get_key_value("recency_weight")
+        .and_then(|v| v.read_float())
+    {
+        search_index.ranking_weights.recency_weight = recency_weight.max(0.0);
+    }
+
     Box::into_raw(search_index)
 }
 
@@ -221,132 +333,131 @@ pub fn add_synthetic_filter(ptr: *mut SearchIndex, filter: &str) -> *mut SearchI
 }
 
 #[wasm_bindgen]
-pub fn request_indexes(ptr: *mut SearchIndex, query: &str) -> String {
-    let mut indexes = try_request_indexes(ptr, query, false);
-    if indexes.is_empty() && !query.trim().is_empty() {
-        debug!({
-            "No index chunks found with strict boundaries. Loading all possible extension chunks."
-        });
-        indexes = try_request_indexes(ptr, query, true);
-    }
+pub fn normalize_query(query: &str, include_characters: &str, index_meta_fields: &str) -> String {
+    let include_characters: Vec<char> = include_characters.chars().collect();
+    let index_meta_fields: Vec<String> = JSONValue::parse(index_meta_fields)
+        .and_then(|fields| fields.iter_array())
+        .map(|fields| {
+            fields
+                .filter_map(|field| field.read_string().map(str::to_owned).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let normalized = NormalizedQuery::new(query, &include_characters, &index_meta_fields);
+    debug!({
+        format! {"Normalized search term to {:?}", normalized}
+    });
 
     let mut output = String::new();
     {
-        let mut arr = write_json::array(&mut output);
-        indexes.into_iter().for_each(|i| {
-            arr.string(&i);
-        });
+        let mut obj = write_json::object(&mut output);
+        obj.string("query", &normalized.query);
+        obj.bool("exact", normalized.exact);
+        obj.number("phrase_slop", normalized.phrase_slop as f64);
     }
-
     output
 }
 
-fn try_request_indexes(ptr: *mut SearchIndex, query: &str, load_all_possible: bool) -> Vec<String> {
+#[wasm_bindgen]
+pub fn request_indexes(ptr: *mut SearchIndex, query: &str, fuzzy: bool) -> String {
+    let search_index = unsafe { Box::from_raw(ptr) };
+    let indexes = search_index.required_index_chunks(query, fuzzy);
+    let _ = Box::into_raw(search_index);
+
+    write_string_array(indexes)
+}
+
+#[wasm_bindgen]
+pub fn request_filter_indexes(ptr: *mut SearchIndex, filters: &str) -> String {
+    let search_index = unsafe { Box::from_raw(ptr) };
+    let indexes = search_index.required_filter_chunks(filters);
+    let _ = Box::into_raw(search_index);
+
+    write_string_array(indexes)
+}
+
+#[wasm_bindgen]
+pub fn request_all_filter_indexes(ptr: *mut SearchIndex) -> String {
+    let search_index = unsafe { Box::from_raw(ptr) };
+    let indexes = search_index.all_filter_chunks();
+    let _ = Box::into_raw(search_index);
+
+    write_string_array(indexes)
+}
+
+#[wasm_bindgen]
+pub fn suggest(ptr: *mut SearchIndex, query: &str, limit: usize) -> String {
     debug!({
-        format! {"Finding the index chunks needed for {:?}", query}
+        format! {"Finding suggestions for {:?}", query}
     });
 
     let search_index = unsafe { Box::from_raw(ptr) };
-    let mut indexes = Vec::new();
-    let terms = query.split(' ');
-
-    for term in terms {
-        let term_index = search_index.chunks.iter().find(|chunk| {
-            if load_all_possible {
-                // Trim chunk boundaries and search terms to the shortest of either,
-                // so that we load any chunk that may contain an extension or prefix of the search term
-                let from_length = term.len().min(chunk.from.len());
-                let to_length = term.len().min(chunk.to.len());
-
-                let (Some(term_pre), Some(chunk_pre)) =
-                    (term.get(0..from_length), chunk.from.get(0..from_length))
-                else {
-                    return false;
-                };
-
-                let (Some(term_post), Some(chunk_post)) =
-                    (term.get(0..to_length), chunk.to.get(0..to_length))
-                else {
-                    return false;
-                };
-
-                term_pre >= chunk_pre && term_post <= chunk_post
-            } else {
-                term >= &chunk.from && term <= &chunk.to
-            }
-        });
-        if let Some(index) = term_index {
-            debug!({
-                format! {"Need {:?} for {:?}", index.hash, term}
-            });
-            indexes.push(index.hash.clone())
-        } else {
-            debug!({
-                format! {"No hash found for {:?}", term}
-            })
-        }
-    }
-
+    let suggestions = search_index.suggest(query, limit);
     let _ = Box::into_raw(search_index);
-    indexes.sort();
-    indexes.dedup();
 
-    indexes
+    write_string_array(suggestions)
 }
 
 #[wasm_bindgen]
-pub fn request_filter_indexes(ptr: *mut SearchIndex, filters: &str) -> String {
+pub fn request_completion_indexes(ptr: *mut SearchIndex, prefix: &str) -> String {
     let search_index = unsafe { Box::from_raw(ptr) };
-    let mut indexes = search_index.filter_chunks(filters).unwrap_or_default();
+    let indexes = search_index.required_completion_chunks(prefix);
     let _ = Box::into_raw(search_index);
-    indexes.sort();
-    indexes.dedup();
+
+    write_string_array(indexes)
+}
+
+#[wasm_bindgen]
+pub fn complete(ptr: *mut SearchIndex, prefix: &str, limit: usize) -> String {
+    debug!({
+        format! {"Completing {:?}", prefix}
+    });
+
+    let search_index = unsafe { Box::from_raw(ptr) };
+
     let mut output = String::new();
     {
         let mut arr = write_json::array(&mut output);
-        indexes.into_iter().for_each(|i| {
-            arr.string(&i);
-        });
+        for (word, page_count) in search_index.complete(prefix, limit) {
+            let mut completion = arr.object();
+            completion.string("word", word);
+            completion.number("count", page_count as f64);
+        }
     }
 
+    let _ = Box::into_raw(search_index);
     output
 }
 
 #[wasm_bindgen]
-pub fn request_all_filter_indexes(ptr: *mut SearchIndex) -> String {
-    debug!({ "Finding all filter chunks" });
+pub fn filters(ptr: *mut SearchIndex) -> String {
+    debug!({ "Returning all loaded filters" });
 
     let search_index = unsafe { Box::from_raw(ptr) };
-    let mut indexes: Vec<String> = search_index
-        .filter_chunks
-        .iter()
-        .map(|(_, chunk)| chunk.into())
-        .collect();
 
-    let _ = Box::into_raw(search_index);
-    indexes.sort();
-    indexes.dedup();
     let mut output = String::new();
     {
-        let mut arr = write_json::array(&mut output);
-        indexes.into_iter().for_each(|i| {
-            arr.string(&i);
-        });
+        let mut obj = write_json::object(&mut output);
+        write_filter_counts(&mut obj, search_index.filter_counts(None));
     }
 
+    let _ = Box::into_raw(search_index);
     output
 }
 
 #[wasm_bindgen]
-pub fn filters(ptr: *mut SearchIndex) -> String {
-    debug!({ "Returning all loaded filters" });
+pub fn nested_filters(ptr: *mut SearchIndex) -> String {
+    debug!({ "Returning all loaded filters, nested beneath their parent values" });
 
     let search_index = unsafe { Box::from_raw(ptr) };
 
     let mut output = String::new();
     {
         let mut obj = write_json::object(&mut output);
-        search_index.get_filters(&mut obj, None);
+        for (filter, values) in search_index.nested_filter_counts() {
+            write_nested_filter_counts(&mut obj.object(&filter), &values);
+        }
     }
 
     let _ = Box::into_raw(search_index);
@@ -354,7 +465,20 @@ pub fn filters(ptr: *mut SearchIndex) -> String {
 }
 
 #[wasm_bindgen]
-pub fn search(ptr: *mut SearchIndex, query: &str, filter: &str, sort: &str, exact: bool) -> String {
+#[allow(clippy::too_many_arguments)]
+pub fn search(
+    ptr: *mut SearchIndex,
+    query: &str,
+    filter: &str,
+    sort: &str,
+    sort_missing: &str,
+    exact: bool,
+    phrase_slop: u32,
+    max_edit_distance: u8,
+    offset: u32,
+    limit: Option<u32>,
+    counts_only: bool,
+) -> String {
     let search_index = unsafe { Box::from_raw(ptr) };
     let mut output = String::new();
     {
@@ -368,46 +492,27 @@ pub fn search(ptr: *mut SearchIndex, query: &str, filter: &str, sort: &str, exac
             }
         }
 
-        let filter_set = search_index.filter(filter);
-        let (unfiltered_results, mut results) = if exact {
-            search_index.exact_term(query, filter_set)
-        } else {
-            search_index.search_term(query, filter_set)
-        };
-        let unfiltered_total = unfiltered_results.len();
-        debug!({ format!("Raw total of {} results", unfiltered_total) });
-        debug!({ format!("Filtered total of {} results", query.len()) });
+        let SearchResponse {
+            results,
+            filtered_total,
+            unfiltered_total,
+            filtered_counts,
+            total_counts,
+        } = search_index.search(&SearchRequest {
+            query,
+            filter,
+            sort,
+            sort_missing,
+            exact,
+            phrase_slop,
+            max_edit_distance,
+            offset: offset as usize,
+            limit: limit.map(|limit| limit as usize),
+            counts_only,
+        });
 
-        {
-            let mut filter_obj = output_obj.object("filtered_counts");
-            search_index.get_filters(
-                &mut filter_obj,
-                Some(results.iter().map(|r| r.page_index).collect()),
-            );
-        }
-        {
-            let mut unfilter_obj = output_obj.object("total_counts");
-            search_index.get_filters(&mut unfilter_obj, Some(unfiltered_results));
-        }
-
-        if let Some((sort, direction)) = sort.split_once(':') {
-            debug!({ format!("Trying to sort by {sort} ({direction})") });
-            if let Some(sorted_pages) = search_index.sorts.get(sort) {
-                debug!({ format!("Found {} pages sorted by {sort}", sorted_pages.len()) });
-                results.retain(|result| sorted_pages.contains(&(result.page_index as u32)));
-
-                for result in results.iter_mut() {
-                    result.page_score = sorted_pages
-                        .iter()
-                        .position(|p| p == &(result.page_index as u32))
-                        .expect("Sorted pages should contain all remaining results")
-                        as f32;
-                    if direction == "asc" {
-                        result.page_score = 0.0 - result.page_score;
-                    }
-                }
-            }
-        }
+        write_filter_counts(&mut output_obj.object("filtered_counts"), filtered_counts);
+        write_filter_counts(&mut output_obj.object("total_counts"), total_counts);
 
         {
             debug!({ "Building the result string" });
@@ -496,11 +601,14 @@ pub fn search(ptr: *mut SearchIndex, query: &str, filter: &str, sort: &str, exac
         }
 
         output_obj.number("unfiltered_total", unfiltered_total as f64);
+        output_obj.number("filtered_total", filtered_total as f64);
 
         if search_index.playground_mode {
             let mut arr = output_obj.array("search_keywords");
-            for term in stems_from_term(query) {
-                arr.string(&term);
+            if let Some(query) = search_index.parse_query(query) {
+                for QueryTerm { term, .. } in query.positive_terms() {
+                    arr.string(term);
+                }
             }
         }
     }
@@ -513,3 +621,36 @@ pub fn search(ptr: *mut SearchIndex, query: &str, filter: &str, sort: &str, exac
 
     output
 }
+
+fn write_string_array(values: Vec<String>) -> String {
+    let mut output = String::new();
+    {
+        let mut arr = write_json::array(&mut output);
+        values.into_iter().for_each(|value| {
+            arr.string(&value);
+        });
+    }
+
+    output
+}
+
+/// Writes the values of a nested filter as `{ count, children }` objects
+fn write_nested_filter_counts(
+    obj: &mut write_json::Object,
+    values: &BTreeMap<String, NestedFilterCount>,
+) {
+    for (value, nested) in values {
+        let mut value_obj = obj.object(value);
+        value_obj.number("count", nested.count as f64);
+        write_nested_filter_counts(&mut value_obj.object("children"), &nested.children);
+    }
+}
+
+fn write_filter_counts(obj: &mut write_json::Object, counts: FilterCounts) {
+    for (filter, values) in counts {
+        let mut filter_obj = obj.object(&filter);
+        for (value, count) in values {
+            filter_obj.number(&value, count as f64);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use super::{IndexChunk, SearchIndex};
-use crate::{util::*, Page};
+use crate::{recency::PageDates, util::*, Page};
 use minicbor::{decode, Decoder};
 
 /*
@@ -10,6 +10,7 @@ use minicbor::{decode, Decoder};
         {
             String,         // page hash
             u32,            // word count
+            f32,            // page weight
         }
         ...
     ]
@@ -32,7 +33,39 @@ use minicbor::{decode, Decoder};
         {
             String,         // sort key
             [ usize, ... ], // sorted page numbers
+            [ u32, ... ],   // rank of each sorted page, shared by pages with equal values
+            [ f64, ... ],   // date of each sorted page, if this is a date sort
         }
+    ],
+    [
+        {
+            String,         // metadata field name
+            [
+                {
+                    String, // start word of index chunk
+                    String, // end word of index chunk
+                    String, // hash of index chunk
+                },
+                ...
+            ]
+        },
+        ...
+    ],
+    [
+        {
+            String,         // stemmed search term
+            [ String, ... ] // stemmed terms or phrases it should also match
+        },
+        ...
+    ],
+    [ String, ... ],        // stop words
+    [
+        {
+            String,         // metadata field name
+            [ usize, ... ], // page numbers with a date in this field
+            [ f64, ... ],   // date of each page
+        },
+        ...
     ]
 }
 */
@@ -56,6 +89,7 @@ impl SearchIndex {
             self.pages.push(Page {
                 hash: consume_string!(decoder),
                 word_count: consume_num!(decoder),
+                page_weight: consume_float!(decoder) as f32,
             });
         }
 
@@ -101,7 +135,107 @@ impl SearchIndex {
                 sorted_pages.push(consume_num!(decoder));
             }
 
-            self.sorts.insert(sort_key, sorted_pages);
+            debug!({ format!("Reading array of page ranks sorted by {:#?}", sort_key) });
+            let rank_num = consume_arr_len!(decoder);
+            let mut ranks = Vec::with_capacity(rank_num as usize);
+            for _ in 0..rank_num {
+                ranks.push(consume_num!(decoder));
+            }
+
+            let date_num = consume_arr_len!(decoder);
+            if date_num > 0 {
+                debug!({ format!("Reading {:#?} dates sorted by {:#?}", date_num, sort_key) });
+                let mut dates = Vec::with_capacity(date_num as usize);
+                for _ in 0..date_num {
+                    dates.push(consume_float!(decoder));
+                }
+                self.sort_dates.insert(
+                    sort_key.clone(),
+                    PageDates::new(self.pages.len(), sorted_pages.clone(), dates),
+                );
+            }
+
+            // Precompute the rank of every page, so that results can look up their rank directly
+            let mut page_ranks = vec![None; self.pages.len()];
+            for (page, rank) in sorted_pages.into_iter().zip(ranks) {
+                if let Some(page_rank) = page_ranks.get_mut(page as usize) {
+                    *page_rank = Some(rank);
+                }
+            }
+            self.sorts.insert(sort_key, page_ranks);
+        }
+
+        debug!({ "Reading meta fields array" });
+        let meta_fields = consume_arr_len!(decoder);
+        debug!({ format!("Reading {:#?} meta fields", meta_fields) });
+        for _ in 0..meta_fields {
+            consume_fixed_arr!(decoder);
+            let field = consume_string!(decoder);
+
+            let index_chunks = consume_arr_len!(decoder);
+            debug!({
+                format!(
+                    "Reading {:#?} index chunks for the {:#?} field",
+                    index_chunks, field
+                )
+            });
+            let mut chunks = Vec::with_capacity(index_chunks as usize);
+            for _ in 0..index_chunks {
+                consume_fixed_arr!(decoder);
+                chunks.push(IndexChunk {
+                    from: consume_string!(decoder),
+                    to: consume_string!(decoder),
+                    hash: consume_string!(decoder),
+                })
+            }
+
+            self.meta_field_chunks.insert(field, chunks);
+        }
+
+        debug!({ "Reading synonyms array" });
+        let synonyms = consume_arr_len!(decoder);
+        debug!({ format!("Reading {:#?} synonyms", synonyms) });
+        for _ in 0..synonyms {
+            consume_fixed_arr!(decoder);
+            let term = consume_string!(decoder);
+
+            let expansions = consume_arr_len!(decoder);
+            let mut term_synonyms = Vec::with_capacity(expansions as usize);
+            for _ in 0..expansions {
+                term_synonyms.push(consume_string!(decoder));
+            }
+
+            self.synonyms.insert(term, term_synonyms);
+        }
+
+        debug!({ "Reading stop words array" });
+        let stop_words = consume_arr_len!(decoder);
+        debug!({ format!("Reading {:#?} stop words", stop_words) });
+        for _ in 0..stop_words {
+            self.stop_words.insert(consume_string!(decoder));
+        }
+
+        debug!({ "Reading meta dates array" });
+        let meta_dates = consume_arr_len!(decoder);
+        debug!({ format!("Reading {:#?} meta date fields", meta_dates) });
+        for _ in 0..meta_dates {
+            consume_fixed_arr!(decoder);
+            let field = consume_string!(decoder);
+
+            let page_num_num = consume_arr_len!(decoder);
+            let mut pages = Vec::with_capacity(page_num_num as usize);
+            for _ in 0..page_num_num {
+                pages.push(consume_num!(decoder));
+            }
+
+            let date_num = consume_arr_len!(decoder);
+            let mut dates = Vec::with_capacity(date_num as usize);
+            for _ in 0..date_num {
+                dates.push(consume_float!(decoder));
+            }
+
+            self.meta_dates
+                .insert(field, PageDates::new(self.pages.len(), pages, dates));
         }
 
         debug!({ "Finished decoding metadata" });
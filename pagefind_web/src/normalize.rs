@@ -0,0 +1,157 @@
+use std::cmp::Ordering;
+
+use crate::punctuation::PUNCTUATION;
+
+/// A search query prepared for the search index, shared by the JS API and native search
+/// so that both read a query the same way.
+#[derive(Debug, PartialEq)]
+pub struct NormalizedQuery {
+    /// The query with words lowercased and punctuation dropped, keeping any query syntax
+    pub query: String,
+    /// Whether the whole query was a single quoted phrase, e.g. `"async runtime"`
+    pub exact: bool,
+    /// How many other words may appear between the terms of an exact phrase, e.g. the 2 in `"async runtime"~2`
+    pub phrase_slop: u32,
+}
+
+impl NormalizedQuery {
+    /// Normalizes a query before it reaches the search index, lowercasing words and dropping punctuation.
+    /// Characters in `include_characters` are kept as-is, and `field:` prefixes are only
+    /// kept for metadata fields listed in `index_meta_fields`.
+    pub fn new(query: &str, include_characters: &[char], index_meta_fields: &[String]) -> Self {
+        let (query, exact, phrase_slop) = match exact_phrase(query) {
+            Some((phrase, slop)) => (phrase, true, slop),
+            None => (query, false, 0),
+        };
+
+        // Query syntax (OR, NOT, -term, field:term, brackets, and quoted phrases) is parsed
+        // by the search index, so it needs to survive normalization.
+        // Exact searches treat the whole query as one phrase.
+        let keep_query_syntax = !exact;
+        let mut words = Vec::new();
+
+        for word in query.split_whitespace() {
+            if keep_query_syntax && (word == "OR" || word == "NOT") {
+                // Boolean operators are only recognized in uppercase
+                words.push(word.to_string());
+                continue;
+            }
+
+            let mut normalized = String::with_capacity(word.len());
+            let mut rest = word;
+            if keep_query_syntax {
+                if let Some(field_len) = field_prefix_len(word, index_meta_fields) {
+                    // Searches scoped to an indexed metadata field, e.g. title:word
+                    normalized.push_str(&word[..field_len]);
+                    rest = &word[field_len..];
+                }
+            }
+
+            let mut only_brackets = true;
+            for c in rest.chars() {
+                let is_query_syntax = keep_query_syntax
+                    && (matches!(c, '"' | '(' | ')') || (c == '-' && only_brackets));
+                if include_characters.contains(&c) || is_query_syntax {
+                    normalized.push(c);
+                } else if !is_punctuation(c) {
+                    normalized.extend(c.to_lowercase());
+                }
+                only_brackets = only_brackets && c == '(';
+            }
+
+            if !normalized.is_empty() {
+                words.push(normalized);
+            }
+        }
+
+        Self {
+            query: words.join(" "),
+            exact,
+            phrase_slop,
+        }
+    }
+}
+
+/// Returns the phrase and slop of a query that is a single quoted phrase,
+/// optionally followed by a slop, e.g. `"async runtime"~2`.
+/// Queries containing several quoted phrases are left to the query parser.
+fn exact_phrase(query: &str) -> Option<(&str, u32)> {
+    let query = query.trim();
+    let (phrase, slop) = match query.rsplit_once('~') {
+        Some((phrase, slop)) if !slop.is_empty() && slop.bytes().all(|b| b.is_ascii_digit()) => {
+            (phrase, slop.parse().unwrap_or(u32::MAX))
+        }
+        _ => (query, 0),
+    };
+
+    let inner = phrase.strip_prefix('"')?.strip_suffix('"')?;
+    (!inner.is_empty() && !inner.contains('"')).then_some((phrase, slop))
+}
+
+/// Returns the length of a leading `field:` on a word, if the field is an indexed metadata field.
+/// The field may be preceded by a negation or brackets, e.g. `-(title:word`.
+fn field_prefix_len(word: &str, index_meta_fields: &[String]) -> Option<usize> {
+    let field_start = word.len() - word.trim_start_matches(['-', '(']).len();
+    let colon = field_start + word[field_start..].find(':')?;
+    let field = &word[field_start..colon];
+
+    let is_field = !field.is_empty()
+        && !field.contains(['-', '('])
+        && colon + 1 < word.len()
+        && index_meta_fields.iter().any(|f| f == field);
+    is_field.then_some(colon + 1)
+}
+
+/// Whether a character is in one of the Unicode punctuation categories,
+/// other than connector punctuation such as `_` which counts as part of a word.
+/// Symbols such as `+` and `$` are kept.
+fn is_punctuation(c: char) -> bool {
+    PUNCTUATION
+        .binary_search_by(|&(start, end)| {
+            if c < start {
+                Ordering::Greater
+            } else if c > end {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(query: &str) -> String {
+        NormalizedQuery::new(query, &[], &[]).query
+    }
+
+    #[test]
+    fn punctuation_categories() {
+        for c in ['.', '!', '(', '»', '—', '‘', '¿', '、', '「', '・'] {
+            assert!(is_punctuation(c), "{c:?} should be punctuation");
+        }
+        // Arabic comma, Devanagari danda, Armenian full stop, Ethiopic full stop, fullwidth comma
+        for c in ['\u{060C}', '\u{0964}', '\u{0589}', '\u{1362}', '\u{FF0C}'] {
+            assert!(is_punctuation(c), "{c:?} should be punctuation");
+        }
+        // Connector punctuation, symbols, letters, and digits
+        for c in [
+            '_', '‿', '＿', '+', '$', '€', '©', 'a', 'ß', 'ж', 'ب', '中', '٣',
+        ] {
+            assert!(!is_punctuation(c), "{c:?} should not be punctuation");
+        }
+    }
+
+    #[test]
+    fn normalizing_non_latin_queries() {
+        assert_eq!(normalize("مرحبا، عالم"), "مرحبا عالم");
+        assert_eq!(normalize("नमस्ते। दुनिया॥"), "नमस्ते दुनिया");
+        assert_eq!(normalize("コーヒー・ミルク"), "コーヒーミルク");
+        assert_eq!(normalize("「東京」、大阪。"), "東京大阪");
+        assert_eq!(normalize("Ελληνικά; ΚΕΙΜΕΝΟ"), "ελληνικά κειμενο");
+        assert_eq!(normalize("«Привет», МИР!"), "привет мир");
+        assert_eq!(normalize("snake_case ＿"), "snake_case ＿");
+    }
+}
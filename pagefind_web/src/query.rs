@@ -0,0 +1,459 @@
+use pagefind_stem::Stemmer;
+
+/*
+Grammar for search queries, loosest binding first:
+
+query   = or
+or      = and { "OR" and }
+and     = unary { unary }
+unary   = ( "-" | "NOT" ) unary | primary
+primary = "(" or ")" | '"' word { word } '"' [ "~" digits ] | field ":" word | word
+
+A phrase followed by ~N (its slop) also matches when up to N other words
+appear between its terms, as long as the terms are still in order.
+
+Stop words are dropped from the query outside of phrases, unless the query
+contains nothing else. A field prefix is only recognized for metadata fields that were indexed
+as their own word space, otherwise the colon is treated as part of the word.
+Malformed queries never error, unmatched brackets and dangling
+operators are ignored so that a partially typed query still searches.
+*/
+
+#[derive(Debug, PartialEq)]
+pub enum QueryNode {
+    /// A single stemmed term, matching any indexed word it is a prefix of
+    Term(String),
+    /// A single stemmed term, matched against the words indexed for a metadata field
+    FieldTerm { field: String, term: String },
+    /// Stemmed terms that must appear in order, with at most `slop` other words between them
+    Phrase { terms: Vec<String>, slop: u32 },
+    /// Pages must match all of these nodes
+    And(Vec<QueryNode>),
+    /// Pages must match any of these nodes
+    Or(Vec<QueryNode>),
+    /// Pages must not match this node
+    Not(Box<QueryNode>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(Vec<String>, u32),
+    Open,
+    Close,
+    Or,
+    Not,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct QueryTerm<'a> {
+    pub term: &'a str,
+    /// The metadata field this term is matched against, or None for page content
+    pub field: Option<&'a str>,
+    /// Whether this term appears in a negated part of the query
+    pub negated: bool,
+}
+
+impl QueryNode {
+    /// Every term in the query
+    pub fn terms(&self) -> Vec<QueryTerm<'_>> {
+        let mut terms = vec![];
+        self.collect_terms(false, &mut terms);
+        terms
+    }
+
+    /// Every term in the query that pages are matched and scored on
+    pub fn positive_terms(&self) -> Vec<QueryTerm<'_>> {
+        self.terms()
+            .into_iter()
+            .filter(|term| !term.negated)
+            .collect()
+    }
+
+    /// Every term and phrase in the query that may have synonyms,
+    /// with the terms of a phrase joined by spaces
+    pub fn synonym_keys(&self) -> Vec<String> {
+        match self {
+            QueryNode::Term(term) => vec![term.clone()],
+            QueryNode::FieldTerm { .. } => vec![],
+            QueryNode::Phrase { terms, .. } => vec![terms.join(" ")],
+            QueryNode::And(nodes) | QueryNode::Or(nodes) => {
+                nodes.iter().flat_map(QueryNode::synonym_keys).collect()
+            }
+            QueryNode::Not(node) => node.synonym_keys(),
+        }
+    }
+
+    fn collect_terms<'a>(&'a self, negated: bool, terms: &mut Vec<QueryTerm<'a>>) {
+        let content_term = |term: &'a String| QueryTerm {
+            term,
+            field: None,
+            negated,
+        };
+        match self {
+            QueryNode::Term(term) => terms.push(content_term(term)),
+            QueryNode::FieldTerm { field, term } => terms.push(QueryTerm {
+                term,
+                field: Some(field),
+                negated,
+            }),
+            QueryNode::Phrase { terms: phrase, .. } => {
+                terms.extend(phrase.iter().map(content_term))
+            }
+            QueryNode::And(nodes) | QueryNode::Or(nodes) => nodes
+                .iter()
+                .for_each(|node| node.collect_terms(negated, terms)),
+            QueryNode::Not(node) => node.collect_terms(!negated, terms),
+        }
+    }
+}
+
+/// Parses a search query, where `is_field` reports whether a
+/// `field:term` prefix names a metadata field that can be searched,
+/// and `is_stop_word` reports whether a word should be left out of the query
+pub fn parse_query(
+    query: &str,
+    stemmer: Option<&Stemmer>,
+    is_field: &dyn Fn(&str) -> bool,
+    is_stop_word: &dyn Fn(&str) -> bool,
+) -> Option<QueryNode> {
+    // A query made up of only stop words still searches for them,
+    // rather than searching for nothing
+    parse_tokens(tokenize(query), stemmer, is_field, is_stop_word)
+        .or_else(|| parse_tokens(tokenize(query), stemmer, is_field, &|_| false))
+}
+
+fn parse_tokens(
+    tokens: Vec<Token>,
+    stemmer: Option<&Stemmer>,
+    is_field: &dyn Fn(&str) -> bool,
+    is_stop_word: &dyn Fn(&str) -> bool,
+) -> Option<QueryNode> {
+    let mut parser = QueryParser {
+        tokens,
+        position: 0,
+        stemmer,
+        is_field,
+        is_stop_word,
+    };
+
+    let mut nodes = vec![];
+    while parser.position < parser.tokens.len() {
+        match parser.parse_or() {
+            Some(node) => nodes.push(node),
+            // Skip over anything we couldn't make sense of, such as a stray closing bracket
+            None => parser.position += 1,
+        }
+    }
+    collapse(nodes, QueryNode::And)
+}
+
+fn tokenize(query: &str) -> Vec<Token> {
+    fn flush(word: &mut String, tokens: &mut Vec<Token>) {
+        if word.is_empty() {
+            return;
+        }
+        tokens.push(match word.as_str() {
+            "OR" => Token::Or,
+            "NOT" => Token::Not,
+            _ => Token::Word(word.clone()),
+        });
+        word.clear();
+    }
+
+    let mut tokens = vec![];
+    let mut word = String::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => {
+                flush(&mut word, &mut tokens);
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                flush(&mut word, &mut tokens);
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                flush(&mut word, &mut tokens);
+                let mut phrase = String::new();
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                    phrase.push(c);
+                }
+
+                let mut slop = 0;
+                if chars.peek() == Some(&'~') {
+                    let mut slop_chars = chars.clone();
+                    slop_chars.next();
+                    let mut digits = String::new();
+                    while let Some(digit) = slop_chars.next_if(char::is_ascii_digit) {
+                        digits.push(digit);
+                    }
+                    if let Ok(parsed_slop) = digits.parse() {
+                        slop = parsed_slop;
+                        chars = slop_chars;
+                    }
+                }
+
+                let phrase_words: Vec<String> = phrase.split_whitespace().map(Into::into).collect();
+                if !phrase_words.is_empty() {
+                    tokens.push(Token::Quoted(phrase_words, slop));
+                }
+            }
+            // A leading hyphen negates the word, hyphens elsewhere are part of the word
+            '-' if word.is_empty() => {
+                if chars.peek().is_some_and(|next| !next.is_whitespace()) {
+                    tokens.push(Token::Not);
+                }
+            }
+            c if c.is_whitespace() => flush(&mut word, &mut tokens),
+            c => word.push(c),
+        }
+    }
+    flush(&mut word, &mut tokens);
+
+    tokens
+}
+
+/// Avoids wrapping a single node in an And or Or group
+fn collapse(
+    mut nodes: Vec<QueryNode>,
+    group: fn(Vec<QueryNode>) -> QueryNode,
+) -> Option<QueryNode> {
+    match nodes.len() {
+        0 => None,
+        1 => nodes.pop(),
+        _ => Some(group(nodes)),
+    }
+}
+
+struct QueryParser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    stemmer: Option<&'a Stemmer>,
+    is_field: &'a dyn Fn(&str) -> bool,
+    is_stop_word: &'a dyn Fn(&str) -> bool,
+}
+
+impl QueryParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn stem(&self, word: &str) -> String {
+        match self.stemmer {
+            Some(stemmer) => stemmer.stem(word).into_owned(),
+            // If we wound up without a stemmer,
+            // charge ahead without stemming.
+            None => word.to_owned(),
+        }
+    }
+
+    fn parse_or(&mut self) -> Option<QueryNode> {
+        let mut nodes = vec![];
+        if let Some(node) = self.parse_and() {
+            nodes.push(node);
+        }
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            if let Some(node) = self.parse_and() {
+                nodes.push(node);
+            }
+        }
+        collapse(nodes, QueryNode::Or)
+    }
+
+    fn parse_and(&mut self) -> Option<QueryNode> {
+        let mut nodes = vec![];
+        while !matches!(self.peek(), None | Some(Token::Or) | Some(Token::Close)) {
+            if let Some(node) = self.parse_unary() {
+                nodes.push(node);
+            }
+        }
+        collapse(nodes, QueryNode::And)
+    }
+
+    fn parse_unary(&mut self) -> Option<QueryNode> {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            return self
+                .parse_unary()
+                .map(|node| QueryNode::Not(Box::new(node)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Option<QueryNode> {
+        let token = self.tokens.get(self.position)?;
+        match token {
+            Token::Open => {
+                self.position += 1;
+                let node = self.parse_or();
+                if self.peek() == Some(&Token::Close) {
+                    self.position += 1;
+                }
+                node
+            }
+            Token::Word(word) => {
+                let node = match word.split_once(':') {
+                    Some((field, term)) if !term.is_empty() && (self.is_field)(field) => {
+                        Some(QueryNode::FieldTerm {
+                            field: field.to_owned(),
+                            term: self.stem(term),
+                        })
+                    }
+                    // Stop words are only searched for as part of a phrase
+                    _ if (self.is_stop_word)(word) => None,
+                    _ => Some(QueryNode::Term(self.stem(word))),
+                };
+                self.position += 1;
+                node
+            }
+            Token::Quoted(words, slop) => {
+                let node = QueryNode::Phrase {
+                    terms: words.iter().map(|word| self.stem(word)).collect(),
+                    slop: *slop,
+                };
+                self.position += 1;
+                Some(node)
+            }
+            Token::Or | Token::Not | Token::Close => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use QueryNode::*;
+
+    fn parse(query: &str) -> Option<QueryNode> {
+        parse_query(query, None, &|field| field == "title", &|word| {
+            matches!(word, "the" | "of" | "a")
+        })
+    }
+
+    fn term(term: &str) -> QueryNode {
+        Term(term.into())
+    }
+
+    fn not(node: QueryNode) -> QueryNode {
+        Not(Box::new(node))
+    }
+
+    #[test]
+    fn operator_precedence() {
+        assert_eq!(
+            parse("cat dog OR fish"),
+            Some(Or(vec![And(vec![term("cat"), term("dog")]), term("fish")]))
+        );
+        assert_eq!(
+            parse("cat OR dog -bird"),
+            Some(Or(vec![
+                term("cat"),
+                And(vec![term("dog"), not(term("bird"))])
+            ]))
+        );
+        assert_eq!(
+            parse("NOT cat OR dog"),
+            Some(Or(vec![not(term("cat")), term("dog")]))
+        );
+        assert_eq!(
+            parse("(cat OR dog) fish"),
+            Some(And(vec![Or(vec![term("cat"), term("dog")]), term("fish")]))
+        );
+        assert_eq!(
+            parse("title:Cats or dogs"),
+            Some(And(vec![
+                FieldTerm {
+                    field: "title".into(),
+                    term: "Cats".into()
+                },
+                term("or"),
+                term("dogs")
+            ]))
+        );
+    }
+
+    #[test]
+    fn unbalanced_parentheses() {
+        assert_eq!(
+            parse("(cat OR dog"),
+            Some(Or(vec![term("cat"), term("dog")]))
+        );
+        assert_eq!(parse("cat) dog"), Some(And(vec![term("cat"), term("dog")])));
+        assert_eq!(parse("((cat"), Some(term("cat")));
+        assert_eq!(parse(")("), None);
+        assert_eq!(parse("cat OR"), Some(term("cat")));
+        assert_eq!(parse("OR NOT"), None);
+    }
+
+    #[test]
+    fn leading_hyphens() {
+        assert_eq!(
+            parse("-cat dog"),
+            Some(And(vec![not(term("cat")), term("dog")]))
+        );
+        assert_eq!(
+            parse("-(cat OR dog) fish"),
+            Some(And(vec![
+                not(Or(vec![term("cat"), term("dog")])),
+                term("fish")
+            ]))
+        );
+        assert_eq!(parse("well-known"), Some(term("well-known")));
+        assert_eq!(parse("- cat"), Some(term("cat")));
+        assert_eq!(parse("-"), None);
+    }
+
+    #[test]
+    fn phrases() {
+        assert_eq!(
+            parse("\"cat dog\"~3 fish"),
+            Some(And(vec![
+                Phrase {
+                    terms: vec!["cat".into(), "dog".into()],
+                    slop: 3
+                },
+                term("fish")
+            ]))
+        );
+        assert_eq!(
+            parse("\"cat dog"),
+            Some(Phrase {
+                terms: vec!["cat".into(), "dog".into()],
+                slop: 0
+            })
+        );
+        assert_eq!(parse("\"\""), None);
+        assert_eq!(parse("\"  \"~2 dog"), Some(term("dog")));
+    }
+
+    #[test]
+    fn stop_words() {
+        assert_eq!(parse("the cat of"), Some(term("cat")));
+        assert_eq!(
+            parse("the of a"),
+            Some(And(vec![term("the"), term("of"), term("a")]))
+        );
+        assert_eq!(
+            parse("\"the cat\" a"),
+            Some(Phrase {
+                terms: vec!["the".into(), "cat".into()],
+                slop: 0
+            })
+        );
+        assert_eq!(parse("-the"), Some(not(term("the"))));
+        assert_eq!(
+            parse("title:the"),
+            Some(FieldTerm {
+                field: "title".into(),
+                term: "the".into()
+            })
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::SearchIndex;
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// The date of each page for a date sort or metadata field
+pub struct PageDates {
+    /// The newest date of any page, which other pages are aged against
+    pub newest: f64,
+    /// The date of each page number, as seconds since the epoch
+    pub dates: Vec<Option<f64>>,
+}
+
+impl PageDates {
+    pub fn new(total_pages: usize, pages: Vec<u32>, dates: Vec<f64>) -> Self {
+        let mut page_dates = vec![None; total_pages];
+        let mut newest = f64::MIN;
+        for (page, date) in pages.into_iter().zip(dates) {
+            if let Some(page_date) = page_dates.get_mut(page as usize) {
+                *page_date = Some(date);
+                newest = newest.max(date);
+            }
+        }
+        Self {
+            newest,
+            dates: page_dates,
+        }
+    }
+}
+
+/// Where the date used to boost recent pages comes from
+#[derive(Debug, Clone)]
+pub enum RecencyField {
+    Sort(String),
+    Meta(String),
+}
+
+/// How quickly the recency boost falls away as pages get older
+#[derive(Debug, Clone, Copy)]
+pub enum RecencyDecay {
+    /// Halves every half life
+    Exponential,
+    /// Falls steadily, reaching zero at twice the half life
+    Linear,
+    /// Stays high for recent pages, then falls quickly after the half life
+    Gauss,
+}
+
+impl RecencyDecay {
+    pub fn parse(decay: &str) -> Option<Self> {
+        match decay {
+            "exponential" => Some(Self::Exponential),
+            "linear" => Some(Self::Linear),
+            "gauss" => Some(Self::Gauss),
+            _ => None,
+        }
+    }
+
+    /// Returns a multiplier between 0.0 and 1.0 for a page of the given age,
+    /// where a page with no age returns 1.0, and a page at the half life returns 0.5
+    fn apply(&self, age: f64, half_life: f64) -> f64 {
+        let age = age.max(0.0) / half_life;
+        match self {
+            Self::Exponential => 0.5_f64.powf(age),
+            Self::Linear => (1.0 - age / 2.0).max(0.0),
+            Self::Gauss => 0.5_f64.powf(age * age),
+        }
+    }
+}
+
+impl SearchIndex {
+    /// Returns the amount to multiply a page's score by, based on how recent the page is.
+    /// Pages without a date are not boosted.
+    pub fn recency_boost(&self, page_index: usize) -> f32 {
+        let weights = &self.ranking_weights;
+        let page_dates = match &weights.recency_field {
+            Some(RecencyField::Sort(sort)) => self.sort_dates.get(sort),
+            Some(RecencyField::Meta(field)) => self.meta_dates.get(field),
+            None => return 1.0,
+        };
+        let Some(page_dates) = page_dates else {
+            return 1.0;
+        };
+        let Some(date) = page_dates.dates.get(page_index).copied().flatten() else {
+            return 1.0;
+        };
+
+        let age = (page_dates.newest - date) / SECONDS_PER_DAY;
+        let decay = weights
+            .recency_decay
+            .apply(age, weights.recency_half_life as f64);
+        1.0 + weights.recency_weight * decay as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn decay(decay: &str, age: f64) -> f64 {
+        RecencyDecay::parse(decay).unwrap().apply(age, 10.0)
+    }
+
+    #[test]
+    fn exponential_decay() {
+        assert_close(decay("exponential", 0.0), 1.0);
+        assert_close(decay("exponential", 5.0), 0.5_f64.sqrt());
+        assert_close(decay("exponential", 10.0), 0.5);
+        assert_close(decay("exponential", 20.0), 0.25);
+        assert_close(decay("exponential", -10.0), 1.0);
+    }
+
+    #[test]
+    fn linear_decay() {
+        assert_close(decay("linear", 0.0), 1.0);
+        assert_close(decay("linear", 5.0), 0.75);
+        assert_close(decay("linear", 10.0), 0.5);
+        assert_close(decay("linear", 20.0), 0.0);
+        assert_close(decay("linear", 30.0), 0.0);
+    }
+
+    #[test]
+    fn gauss_decay() {
+        assert_close(decay("gauss", 0.0), 1.0);
+        assert_close(decay("gauss", 5.0), 0.5_f64.powf(0.25));
+        assert_close(decay("gauss", 10.0), 0.5);
+        assert_close(decay("gauss", 20.0), 0.0625);
+        assert!(decay("gauss", 5.0) > decay("exponential", 5.0));
+        assert!(decay("gauss", 20.0) < decay("exponential", 20.0));
+    }
+
+    #[test]
+    fn unknown_decay() {
+        assert!(RecencyDecay::parse("Linear").is_none());
+        assert!(RecencyDecay::parse("").is_none());
+    }
+
+    #[test]
+    fn boosting_recent_pages() {
+        let mut index = SearchIndex::empty(None);
+        index.sort_dates.insert(
+            "date".into(),
+            PageDates::new(
+                4,
+                vec![0, 1, 5],
+                vec![0.0, 10.0 * SECONDS_PER_DAY, 20.0 * SECONDS_PER_DAY],
+            ),
+        );
+        assert_eq!(index.recency_boost(0), 1.0);
+
+        index.ranking_weights.recency_field = Some(RecencyField::Sort("date".into()));
+        index.ranking_weights.recency_half_life = 10.0;
+        index.ranking_weights.recency_weight = 2.0;
+        assert_eq!(index.recency_boost(1), 3.0);
+        assert_eq!(index.recency_boost(0), 2.0);
+        assert_eq!(index.recency_boost(2), 1.0);
+        assert_eq!(index.recency_boost(5), 1.0);
+
+        index.ranking_weights.recency_field = Some(RecencyField::Meta("date".into()));
+        assert_eq!(index.recency_boost(1), 1.0);
+    }
+}
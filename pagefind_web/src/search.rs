@@ -1,11 +1,15 @@
 use std::{
     borrow::Cow,
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, HashSet},
     ops::{Add, AddAssign, Div},
 };
 
-use crate::{util::*, PageWord, RankingWeights};
+use crate::{
+    query::{parse_query, QueryNode},
+    util::*,
+    PageWord, RankingWeights,
+};
 use bit_set::BitSet;
 use pagefind_stem::Stemmer;
 
@@ -25,6 +29,12 @@ struct MatchingPageWord<'a> {
     word_str: &'a str,
     length_bonus: f32,
     num_pages_matching: usize,
+    /// Identifies the query term this word was matched from,
+    /// so that extensions of one term are grouped when measuring term proximity
+    term_id: usize,
+    /// Words matched in a metadata field count towards a page's score,
+    /// but don't have a location in the page content
+    from_meta_field: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -80,6 +90,104 @@ fn word_length_bonus(differential: u8, term_similarity_ranking: f32) -> f32 {
     (base * term_similarity_ranking).exp() / max_value
 }
 
+/// Returns the maximum number of edits a fuzzy match may make to the given term.
+/// Short terms are given less leeway, as a single edit to a short word
+/// is usually enough to turn it into an unrelated word.
+pub(crate) fn fuzzy_distance_for_term(term: &str, max_edit_distance: u8) -> usize {
+    let allowed = match term.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    };
+    allowed.min(max_edit_distance as usize)
+}
+
+/// Returns the Damerau-Levenshtein distance (optimal string alignment variant)
+/// between two words, or None if the distance exceeds `max_distance`.
+pub(crate) fn edit_distance(a: &str, b: &str, max_distance: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max_distance {
+        return None;
+    }
+
+    let mut prev_prev_row: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev_row: Vec<usize> = (0..=b.len()).collect();
+    let mut row: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        row[0] = i;
+        let mut row_min = i;
+        for j in 1..=b.len() {
+            let substitution_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            row[j] = (prev_row[j] + 1)
+                .min(row[j - 1] + 1)
+                .min(prev_row[j - 1] + substitution_cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(prev_prev_row[j - 2] + 1);
+            }
+            row_min = row_min.min(row[j]);
+        }
+        if row_min > max_distance {
+            // No cell in this row is within the distance, so no later row can be either
+            return None;
+        }
+        std::mem::swap(&mut prev_prev_row, &mut prev_row);
+        std::mem::swap(&mut prev_row, &mut row);
+    }
+
+    let distance = prev_row[b.len()];
+    (distance <= max_distance).then_some(distance)
+}
+
+/// Returns a score between 0.0 and 1.0 for how close together the distinct query terms
+/// appear on a page, given the location and term id of every matched word.
+/// 1.0 implies the terms appear side by side, and the score decays
+/// as the smallest window of the page containing every term grows.
+/// Pages that only match one term score 0.0.
+fn term_proximity(mut locations: Vec<(u32, usize)>) -> f32 {
+    locations.sort_unstable();
+
+    let Some(max_term_id) = locations.iter().map(|(_, term_id)| *term_id).max() else {
+        return 0.0;
+    };
+    let mut term_counts = vec![0; max_term_id + 1];
+    for (_, term_id) in &locations {
+        term_counts[*term_id] += 1;
+    }
+    let distinct_terms = term_counts.iter().filter(|count| **count > 0).count();
+    if distinct_terms < 2 {
+        return 0.0;
+    }
+
+    // Slide a window over the locations to find the smallest span containing every term
+    let mut window_counts = vec![0; max_term_id + 1];
+    let mut window_terms = 0;
+    let mut window_start = 0;
+    let mut min_span = u32::MAX;
+    for (end_location, term_id) in &locations {
+        window_counts[*term_id] += 1;
+        if window_counts[*term_id] == 1 {
+            window_terms += 1;
+        }
+        while window_terms == distinct_terms {
+            let (start_location, start_term_id) = locations[window_start];
+            min_span = min_span.min(end_location - start_location);
+            window_counts[start_term_id] -= 1;
+            if window_counts[start_term_id] == 0 {
+                window_terms -= 1;
+            }
+            window_start += 1;
+        }
+    }
+
+    if min_span == 0 {
+        // Every term matched the same location, i.e. parts of one compound word
+        return 1.0;
+    }
+    ((distinct_terms - 1) as f32 / min_span as f32).min(1.0)
+}
+
 fn calculate_bm25_word_score(
     BM25Params {
         weighted_term_frequency,
@@ -151,196 +259,78 @@ fn calculate_individual_word_score(
 }
 
 impl SearchIndex {
+    /// Searches for the whole term as a single phrase,
+    /// allowing up to `slop` other words to appear between its terms
     pub fn exact_term(
         &self,
         term: &str,
         filter_results: Option<BitSet>,
+        slop: u32,
     ) -> (Vec<usize>, Vec<PageSearchResult>) {
         debug!({
-            format! {"Searching {:?}", term}
+            format! {"Searching for the phrase {:?} with a slop of {}", term, slop}
         });
 
-        let mut unfiltered_results: Vec<usize> = vec![];
-        let mut maps = Vec::new();
-        let mut words = Vec::new();
-        for term in stems_from_term(term) {
-            if let Some(word_index) = self.words.get(term.as_ref()) {
-                words.extend(word_index);
-                let mut set = BitSet::new();
-                for page in word_index {
-                    set.insert(page.page as usize);
-                }
-                maps.push(set);
-            } else {
-                // If we can't find this word, there are obviously no exact matches
-                return (vec![], vec![]);
-            }
-        }
-
-        if !maps.is_empty() {
-            let map = match intersect_maps(maps) {
-                Some(maps) => maps,
-                // Results must exist at this point.
-                None => std::process::abort(),
-            };
-            unfiltered_results.extend(map.iter());
-            maps = vec![map];
-        }
-
-        if let Some(filter) = filter_results {
-            maps.push(filter);
-        }
+        let terms: Vec<String> = stems_from_term(term, self.stemmer.as_ref())
+            .into_iter()
+            .map(Cow::into_owned)
+            .collect();
+        let query = (!terms.is_empty()).then_some(QueryNode::Phrase { terms, slop });
 
-        let results = match intersect_maps(maps) {
-            Some(map) => map,
-            None => return (vec![], vec![]),
-        };
-
-        let mut pages: Vec<PageSearchResult> = vec![];
-
-        for page_index in results.iter() {
-            let word_locations: Vec<Vec<(u8, u32)>> = words
-                .iter()
-                .filter_map(|p| {
-                    if p.page as usize == page_index {
-                        Some(p.locs.iter().map(|d| *d).collect())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            debug!({
-                format! {"Word locations {:?}", word_locations}
-            });
-
-            if let (Some(loc_0), Some(loc_rest)) = (word_locations.get(0), word_locations.get(1..))
-            {
-                'indexes: for (_, pos) in loc_0 {
-                    let mut i = *pos;
-                    for subsequent in loc_rest {
-                        i += 1;
-                        // Test each subsequent word map to try and find a contiguous block
-                        if !subsequent.iter().any(|(_, p)| *p == i) {
-                            continue 'indexes;
-                        }
-                    }
-                    let page = match self.pages.get(page_index) {
-                        Some(p) => p,
-                        None => std::process::abort(),
-                    };
-                    let search_result = PageSearchResult {
-                        page: page.hash.clone(),
-                        page_index,
-                        page_score: 1.0,
-                        page_length: page.word_count,
-                        word_locations: ((*pos..=i).map(|w| BalancedWordScore {
-                            weight: 1,
-                            balanced_score: 1.0,
-                            word_location: w,
-                            verbose_word_info: None, // TODO: bring playground info to quoted searches
-                        }))
-                        .collect(),
-                        verbose_scores: None, // TODO: bring playground info to quoted searches
-                    };
-                    pages.push(search_result);
-                    break 'indexes;
-                }
-            } else {
-                let page = match self.pages.get(page_index) {
-                    Some(p) => p,
-                    None => std::process::abort(),
-                };
-                if let Some(loc_0) = word_locations.get(0) {
-                    let search_result = PageSearchResult {
-                        page: page.hash.clone(),
-                        page_index,
-                        page_score: 1.0,
-                        page_length: page.word_count,
-                        word_locations: loc_0
-                            .iter()
-                            .map(|(weight, word_location)| BalancedWordScore {
-                                weight: *weight,
-                                balanced_score: *weight as f32,
-                                word_location: *word_location,
-                                verbose_word_info: None, // TODO: bring playground info to quoted searches
-                            })
-                            .collect(),
-                        verbose_scores: None, // TODO: bring playground info to quoted searches
-                    };
-                    pages.push(search_result);
-                }
-            }
-        }
-
-        (unfiltered_results, pages)
+        // Exact searches never match fuzzily
+        self.search_query(query.as_ref(), filter_results, 0)
     }
 
     pub fn search_term(
         &self,
         term: &str,
         filter_results: Option<BitSet>,
+        max_edit_distance: u8,
     ) -> (Vec<usize>, Vec<PageSearchResult>) {
         debug!({
             format! {"Searching {:?}", term}
         });
 
+        let query = self.parse_query(term);
+        if query.is_none() && !term.trim().is_empty() {
+            // A search term made up of only query syntax can't match anything
+            return (vec![], vec![]);
+        }
+
+        self.search_query(query.as_ref(), filter_results, max_edit_distance)
+    }
+
+    /// Finds and scores the pages matching a parsed query,
+    /// or every page (within the filters) when there is no query
+    fn search_query(
+        &self,
+        query: Option<&QueryNode>,
+        filter_results: Option<BitSet>,
+        max_edit_distance: u8,
+    ) -> (Vec<usize>, Vec<PageSearchResult>) {
         let total_pages = self.pages.len();
 
         let mut unfiltered_results: Vec<usize> = vec![];
         let mut maps = Vec::new();
         let mut words: Vec<MatchingPageWord> = Vec::new();
-        let split_term = stems_from_term(term);
-
-        for term in split_term.iter() {
-            let mut word_maps = Vec::new();
-            for (word, word_index) in self.find_word_extensions(&term) {
-                let length_differential: u8 = (word.len().abs_diff(term.len()) + 1)
-                    .try_into()
-                    .unwrap_or(std::u8::MAX);
-
-                words.extend(word_index.iter().map(|pageword| MatchingPageWord {
-                    word: pageword,
-                    word_str: &word,
-                    length_bonus: word_length_bonus(
-                        length_differential,
-                        self.ranking_weights.term_similarity,
-                    ),
-                    num_pages_matching: word_index.len(),
-                }));
-                let mut set = BitSet::new();
-                for page in word_index {
-                    set.insert(page.page as usize);
-                }
-                word_maps.push(set);
-            }
-            if let Some(result) = union_maps(word_maps) {
-                maps.push(result);
-            }
-        }
-        // In the case where a search term was passed, but not found,
-        // make sure we cause the entire search to return no results.
-        if !split_term.is_empty() && maps.is_empty() {
-            maps.push(BitSet::new());
-        }
 
-        if !maps.is_empty() {
-            let map = match intersect_maps(maps) {
-                Some(maps) => maps,
-                // Results must exist at this point.
-                None => std::process::abort(),
-            };
+        if let Some(query) = query {
+            debug!({
+                format! {"Parsed the query {:?}", query}
+            });
+            // In the case where a search term was passed, but not found,
+            // make sure we cause the entire search to return no results.
+            let map = self
+                .evaluate_query(query, max_edit_distance, &mut words)
+                .unwrap_or_default();
             unfiltered_results.extend(map.iter());
-            maps = vec![map];
+            maps.push(map);
         }
 
         if let Some(filter) = filter_results {
             maps.push(filter);
         } else if maps.is_empty() {
-            let mut all_filter = BitSet::new();
-            for i in 0..self.pages.len() {
-                all_filter.insert(i);
-            }
-            maps.push(all_filter);
+            maps.push(self.all_pages());
         }
 
         let results = match intersect_maps(maps) {
@@ -357,7 +347,7 @@ impl SearchIndex {
             let mut word_locations: Vec<_> = words
                 .iter()
                 .filter_map(|w| {
-                    if w.word.page as usize == page_index {
+                    if w.word.page as usize == page_index && !w.from_meta_field {
                         Some(
                             w.word
                                 .locs
@@ -427,6 +417,23 @@ impl SearchIndex {
                 ));
             }
 
+            for meta_field_word in words
+                .iter()
+                .filter(|w| w.word.page as usize == page_index && w.from_meta_field)
+            {
+                weighted_words
+                    .entry(meta_field_word.word_str)
+                    .or_default()
+                    .add_assign(
+                        meta_field_word
+                            .word
+                            .locs
+                            .iter()
+                            .map(|(weight, _)| *weight as usize)
+                            .sum::<usize>(),
+                    );
+            }
+
             debug!({
                 format! {"Coerced to unique locations {:?}", unique_word_locations}
             });
@@ -477,7 +484,21 @@ impl SearchIndex {
                         score.score
                     });
 
-            let page_score = word_scores.sum();
+            let page_score: f32 = word_scores.sum();
+
+            let proximity = term_proximity(
+                words
+                    .iter()
+                    .filter(|w| w.word.page as usize == page_index && !w.from_meta_field)
+                    .flat_map(|w| w.word.locs.iter().map(|(_, loc)| (*loc, w.term_id)))
+                    .collect(),
+            );
+            debug!({
+                format! {"Query terms have a proximity of {:?}", proximity}
+            });
+            let page_score = page_score * (1.0 + self.ranking_weights.term_proximity * proximity);
+            let page_score = page_score * page.page_weight.powf(self.ranking_weights.page_weight);
+            let page_score = page_score * self.recency_boost(page_index);
 
             let search_result = PageSearchResult {
                 page: page.hash.clone(),
@@ -505,47 +526,351 @@ impl SearchIndex {
         (unfiltered_results, pages)
     }
 
-    fn find_word_extensions(&self, term: &str) -> Vec<(&String, &Vec<PageWord>)> {
-        let mut extensions = vec![];
-        let mut longest_prefix = None;
-        for (key, results) in self.words.iter() {
-            if key.starts_with(term) {
+    /// Parses a search query, recognizing `field:term` for any metadata fields with their own index
+    /// and leaving out the stop words for this language
+    pub fn parse_query(&self, query: &str) -> Option<QueryNode> {
+        parse_query(
+            query,
+            self.stemmer.as_ref(),
+            &|field| self.meta_field_chunks.contains_key(field),
+            &|word| self.stop_words.contains(word),
+        )
+    }
+
+    /// Returns the pages matching the given node of a query, and collects the words
+    /// that should be used to score them. Returns None if the node only contained terms that
+    /// don't exist in the index, which are ignored rather than failing the whole query.
+    fn evaluate_query<'a>(
+        &'a self,
+        node: &QueryNode,
+        max_edit_distance: u8,
+        words: &mut Vec<MatchingPageWord<'a>>,
+    ) -> Option<BitSet> {
+        match node {
+            QueryNode::Term(term) => {
+                let term_id = next_term_id(words);
+                let map = self.match_term(term, None, max_edit_distance, words);
+                self.match_synonyms(term, term_id, map, words)
+            }
+            QueryNode::FieldTerm { field, term } => {
+                self.match_term(term, Some(field), max_edit_distance, words)
+            }
+            QueryNode::Phrase { terms, slop } => {
+                let term_id = next_term_id(words);
+                let map = self.match_phrase(terms, *slop, words);
+                self.match_synonyms(&terms.join(" "), term_id, Some(map), words)
+            }
+            QueryNode::And(nodes) => {
+                let mut has_positive_nodes = false;
+                let mut included = vec![];
+                let mut excluded = vec![];
+                for node in nodes {
+                    match node {
+                        // Negated nodes are subtracted from the group, rather than matching all other pages.
+                        // Words are only collected for scoring from the positive parts of a query,
+                        // and negated terms are never fuzzy matched.
+                        QueryNode::Not(negated) => {
+                            excluded.extend(self.evaluate_query(negated, 0, &mut vec![]))
+                        }
+                        _ => {
+                            has_positive_nodes = true;
+                            included.extend(self.evaluate_query(node, max_edit_distance, words));
+                        }
+                    }
+                }
+
+                let mut map = match intersect_maps(included) {
+                    Some(map) => map,
+                    None if has_positive_nodes => return None,
+                    None => self.all_pages(),
+                };
+                for excluded in excluded {
+                    map.difference_with(&excluded);
+                }
+                Some(map)
+            }
+            QueryNode::Or(nodes) => union_maps(
+                nodes
+                    .iter()
+                    .filter_map(|node| self.evaluate_query(node, max_edit_distance, words))
+                    .collect(),
+            ),
+            QueryNode::Not(negated) => {
+                let mut map = self.all_pages();
+                if let Some(excluded) = self.evaluate_query(negated, 0, &mut vec![]) {
+                    map.difference_with(&excluded);
+                }
+                Some(map)
+            }
+        }
+    }
+
+    /// Matches pages containing words that extend (or fuzzy match) the term,
+    /// searching the words of the given metadata field rather than the page content if provided
+    fn match_term<'a>(
+        &'a self,
+        term: &str,
+        field: Option<&str>,
+        max_edit_distance: u8,
+        words: &mut Vec<MatchingPageWord<'a>>,
+    ) -> Option<BitSet> {
+        let word_map = match field {
+            Some(field) => self.meta_field_words.get(field)?,
+            None => &self.words,
+        };
+
+        let term_id = next_term_id(words);
+        let mut word_maps = Vec::new();
+        let extensions = find_word_extensions(word_map, term);
+        let fuzzy_matches = find_fuzzy_matches(word_map, term, max_edit_distance, &extensions);
+        let matches = extensions
+            .into_iter()
+            .map(|(word, word_index)| (word, word_index, 0))
+            .chain(fuzzy_matches);
+
+        for (word, word_index, edit_distance) in matches {
+            let length_differential: u8 = (word.len().abs_diff(term.len()) + 1)
+                .try_into()
+                .unwrap_or(u8::MAX);
+            // Fuzzy matches are penalized for each edit they needed to match the term
+            let length_bonus =
+                word_length_bonus(length_differential, self.ranking_weights.term_similarity)
+                    / (edit_distance + 1) as f32;
+
+            words.extend(word_index.iter().map(|pageword| MatchingPageWord {
+                word: pageword,
+                word_str: word,
+                length_bonus,
+                num_pages_matching: word_index.len(),
+                term_id,
+                from_meta_field: field.is_some(),
+            }));
+            let mut set = BitSet::new();
+            for page in word_index {
+                set.insert(page.page as usize);
+            }
+            word_maps.push(set);
+        }
+        union_maps(word_maps)
+    }
+
+    /// Matches pages containing the exact terms of a phrase in order,
+    /// with at most `slop` other words between them
+    fn match_phrase<'a>(
+        &'a self,
+        terms: &[String],
+        slop: u32,
+        words: &mut Vec<MatchingPageWord<'a>>,
+    ) -> BitSet {
+        let mut phrase_words = Vec::with_capacity(terms.len());
+        for term in terms {
+            match self.words.get_key_value(term) {
+                Some(word) => phrase_words.push(word),
+                // If we can't find this word, there are obviously no phrase matches
+                None => return BitSet::new(),
+            }
+        }
+
+        let maps = phrase_words
+            .iter()
+            .map(|(_, word_index)| word_index.iter().map(|p| p.page as usize).collect())
+            .collect();
+        let Some(candidates) = intersect_maps(maps) else {
+            return BitSet::new();
+        };
+
+        let mut matching_pages = BitSet::new();
+        for page_index in candidates.iter() {
+            let page_words: Vec<&PageWord> = phrase_words
+                .iter()
+                .filter_map(|(_, word_index)| {
+                    word_index.iter().find(|p| p.page as usize == page_index)
+                })
+                .collect();
+            if contains_phrase(&page_words, slop) {
+                matching_pages.insert(page_index);
+            }
+        }
+
+        let length_bonus = word_length_bonus(1, self.ranking_weights.term_similarity);
+        let first_term_id = next_term_id(words);
+        for (i, (word_str, word_index)) in phrase_words.into_iter().enumerate() {
+            words.extend(
+                word_index
+                    .iter()
+                    .filter(|p| matching_pages.contains(p.page as usize))
+                    .map(|pageword| MatchingPageWord {
+                        word: pageword,
+                        word_str,
+                        length_bonus,
+                        num_pages_matching: word_index.len(),
+                        term_id: first_term_id + i,
+                        from_meta_field: false,
+                    }),
+            );
+        }
+
+        matching_pages
+    }
+
+    /// Adds the pages matching any synonyms of a term (or space separated phrase)
+    /// to the pages it matched itself. Words matched through a synonym are collected
+    /// under the original term's id, so that they score as alternatives to it.
+    fn match_synonyms<'a>(
+        &'a self,
+        key: &str,
+        term_id: usize,
+        map: Option<BitSet>,
+        words: &mut Vec<MatchingPageWord<'a>>,
+    ) -> Option<BitSet> {
+        let Some(synonyms) = self.synonyms.get(key) else {
+            return map;
+        };
+
+        let first_synonym_word = words.len();
+        let mut maps: Vec<BitSet> = map.into_iter().collect();
+        for synonym in synonyms {
+            let synonym_terms: Vec<String> = synonym.split(' ').map(Into::into).collect();
+            match synonym_terms.as_slice() {
+                // Synonyms are configured deliberately, so are never fuzzy matched
+                [synonym_term] => maps.extend(self.match_term(synonym_term, None, 0, words)),
+                _ => maps.push(self.match_phrase(&synonym_terms, 0, words)),
+            }
+        }
+        for word in &mut words[first_synonym_word..] {
+            word.term_id = term_id;
+        }
+
+        union_maps(maps)
+    }
+
+    /// Every word that the query may match through synonyms
+    pub fn synonym_terms(&self, query: &QueryNode) -> Vec<String> {
+        query
+            .synonym_keys()
+            .iter()
+            .filter_map(|key| self.synonyms.get(key))
+            .flatten()
+            .flat_map(|synonym| synonym.split(' '))
+            .map(Into::into)
+            .collect()
+    }
+
+    fn all_pages(&self) -> BitSet {
+        let mut all_pages = BitSet::with_capacity(self.pages.len());
+        for i in 0..self.pages.len() {
+            all_pages.insert(i);
+        }
+        all_pages
+    }
+}
+
+fn find_word_extensions<'a>(
+    words: &'a BTreeMap<String, Vec<PageWord>>,
+    term: &str,
+) -> Vec<(&'a String, &'a Vec<PageWord>)> {
+    let mut extensions = vec![];
+    let mut longest_prefix = None;
+    for (key, results) in words.iter() {
+        if key.starts_with(term) {
+            debug!({
+                format! {"Adding {:#?} to the query", key}
+            });
+            extensions.push((key, results));
+        } else if term.starts_with(key)
+            && key.len() > longest_prefix.map(String::len).unwrap_or_default()
+        {
+            longest_prefix = Some(key);
+        }
+    }
+    if extensions.is_empty() {
+        debug!({ "No word extensions found, checking the inverse" });
+        if let Some(longest_prefix) = longest_prefix {
+            if let Some(results) = words.get(longest_prefix) {
                 debug!({
-                    format! {"Adding {:#?} to the query", key}
+                    format! {"Adding the prefix {:#?} to the query", longest_prefix}
                 });
-                extensions.push((key, results));
-            } else if term.starts_with(key)
-                && key.len() > longest_prefix.map(String::len).unwrap_or_default()
-            {
-                longest_prefix = Some(key);
+                extensions.push((longest_prefix, results));
             }
         }
-        if extensions.is_empty() {
-            debug!({ "No word extensions found, checking the inverse" });
-            if let Some(longest_prefix) = longest_prefix {
-                if let Some(results) = self.words.get(longest_prefix) {
-                    debug!({
-                        format! {"Adding the prefix {:#?} to the query", longest_prefix}
-                    });
-                    extensions.push((longest_prefix, results));
-                }
-            }
+    }
+    extensions
+}
+
+fn find_fuzzy_matches<'a>(
+    words: &'a BTreeMap<String, Vec<PageWord>>,
+    term: &str,
+    max_edit_distance: u8,
+    existing_matches: &[(&String, &Vec<PageWord>)],
+) -> Vec<(&'a String, &'a Vec<PageWord>, usize)> {
+    let max_distance = fuzzy_distance_for_term(term, max_edit_distance);
+    if max_distance == 0 {
+        return vec![];
+    }
+
+    let existing_matches: HashSet<&str> = existing_matches
+        .iter()
+        .map(|(existing, _)| existing.as_str())
+        .collect();
+    let mut fuzzy_matches = vec![];
+    for (key, results) in words.iter() {
+        if existing_matches.contains(key.as_str()) {
+            continue;
+        }
+        if let Some(distance) = edit_distance(term, key, max_distance) {
+            debug!({
+                format! {"Adding the fuzzy match {:#?} (distance {}) to the query", key, distance}
+            });
+            fuzzy_matches.push((key, results, distance));
         }
-        extensions
     }
+    fuzzy_matches
+}
+
+/// Returns an id for the next query term to match words for.
+/// Words are collected in query order, so this only needs to follow the last collected word.
+fn next_term_id(words: &[MatchingPageWord]) -> usize {
+    words.last().map(|w| w.term_id + 1).unwrap_or_default()
+}
+
+/// Tests whether the words (in phrase order) appear in order on a page,
+/// with at most `slop` other words between the first and last word of the phrase
+fn contains_phrase(page_words: &[&PageWord], slop: u32) -> bool {
+    let Some((first_word, subsequent_words)) = page_words.split_first() else {
+        return false;
+    };
+    first_word.locs.iter().any(|(_, start)| {
+        // Taking the earliest position of each subsequent word keeps the phrase as short as possible
+        let mut previous = *start;
+        for word in subsequent_words {
+            let next = word
+                .locs
+                .iter()
+                .map(|(_, pos)| *pos)
+                .filter(|pos| *pos > previous)
+                .min();
+            match next {
+                Some(next) => previous = next,
+                None => return false,
+            }
+        }
+        let words_between = previous - start - subsequent_words.len() as u32;
+        words_between <= slop
+    })
 }
 
-pub fn stems_from_term(term: &str) -> Vec<Cow<str>> {
+/// Stems each word of an exact phrase. Stop words are kept,
+/// as they still need to match for the phrase to match.
+pub fn stems_from_term<'a>(term: &'a str, stemmer: Option<&Stemmer>) -> Vec<Cow<'a, str>> {
     if term.trim().is_empty() {
         return vec![];
     }
-    let stemmer = Stemmer::try_create_default();
     term.split(' ')
-        .map(|word| match &stemmer {
-            Ok(stemmer) => stemmer.stem(word),
+        .map(|word| match stemmer {
+            Some(stemmer) => stemmer.stem(word),
             // If we wound up without a stemmer,
             // charge ahead without stemming.
-            Err(_) => word.into(),
+            None => word.into(),
         })
         .collect()
 }
@@ -0,0 +1,174 @@
+use std::cmp::Ordering;
+
+use crate::search::PageSearchResult;
+use crate::util::*;
+use crate::SearchIndex;
+
+/// A single key that results can be sorted by
+enum SortKey<'a> {
+    Relevance,
+    /// The rank of each page number, if that page is tagged with this sort
+    Ranks(&'a [Option<u32>]),
+}
+
+/// Where to put results that aren't tagged with a sort
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MissingValues {
+    Drop,
+    First,
+    Last,
+}
+
+impl SearchIndex {
+    /// Sorts results by a comma-separated list of sorts and directions,
+    /// e.g. `priority:desc,date:desc,relevance:desc`, where each sort breaks the ties
+    /// of the sorts before it. Results that tie on every sort stay in order of relevance.
+    /// Results that aren't tagged with a sort are dropped, or placed `first` or `last`
+    /// for that sort regardless of its direction.
+    pub fn sort_results(&self, results: &mut Vec<PageSearchResult>, sort: &str, missing: &str) {
+        let keys: Vec<(SortKey, bool)> = sort
+            .split(',')
+            .filter_map(|entry| {
+                let (sort, direction) = entry.split_once(':')?;
+                let (sort, descending) = (sort.trim(), direction.trim() == "desc");
+                debug!({ format!("Trying to sort by {sort} ({direction})") });
+                if sort == "relevance" {
+                    return Some((SortKey::Relevance, descending));
+                }
+
+                let Some(page_ranks) = self.sorts.get(sort) else {
+                    debug!({ format!("No pages are sorted by {sort}") });
+                    return None;
+                };
+                Some((SortKey::Ranks(page_ranks), descending))
+            })
+            .collect();
+        if keys.is_empty() {
+            return;
+        }
+
+        let missing = match missing {
+            "drop" => MissingValues::Drop,
+            "first" => MissingValues::First,
+            _ => MissingValues::Last,
+        };
+        debug!({ format!("Sorting results without a sort value {missing:?}") });
+        let rank = |ranks: &[Option<u32>], result: &PageSearchResult| {
+            ranks.get(result.page_index).copied().flatten()
+        };
+
+        if missing == MissingValues::Drop {
+            results.retain(|result| {
+                keys.iter().all(|(key, _)| match key {
+                    SortKey::Relevance => true,
+                    SortKey::Ranks(ranks) => rank(ranks, result).is_some(),
+                })
+            });
+        }
+
+        results.sort_by(|a, b| {
+            keys.iter()
+                .map(|(key, descending)| {
+                    let ordering = match key {
+                        SortKey::Relevance => a.page_score.total_cmp(&b.page_score),
+                        SortKey::Ranks(ranks) => match (rank(ranks, a), rank(ranks, b)) {
+                            (Some(a), Some(b)) => a.cmp(&b),
+                            // Missing values are placed the same way in either direction
+                            (a, b) => {
+                                let ordering = a.is_some().cmp(&b.is_some());
+                                return match missing {
+                                    MissingValues::Last => ordering.reverse(),
+                                    _ => ordering,
+                                };
+                            }
+                        },
+                    };
+                    if *descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        // Results are ordered by their score, so replace each score with its sorted position
+        let total = results.len();
+        for (i, result) in results.iter_mut().enumerate() {
+            result.page_score = (total - i) as f32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_index() -> SearchIndex {
+        let mut index = SearchIndex::empty(None);
+        index
+            .sorts
+            .insert("priority".into(), vec![Some(1), None, Some(0), Some(1)]);
+        index
+            .sorts
+            .insert("date".into(), vec![Some(2), Some(0), None, Some(1)]);
+        index
+    }
+
+    /// Results for pages 0 to 3, with page 0 the most relevant
+    fn results() -> Vec<PageSearchResult> {
+        (0..4)
+            .map(|page_index| PageSearchResult {
+                page: format!("page_{page_index}"),
+                page_index,
+                page_length: 10,
+                page_score: (4 - page_index) as f32,
+                word_locations: vec![],
+                verbose_scores: None,
+            })
+            .collect()
+    }
+
+    fn sort(sort: &str, missing: &str) -> Vec<usize> {
+        let mut results = results();
+        sorted_index().sort_results(&mut results, sort, missing);
+        results.iter().map(|result| result.page_index).collect()
+    }
+
+    #[test]
+    fn missing_values() {
+        assert_eq!(sort("priority:asc", "last"), vec![2, 0, 3, 1]);
+        assert_eq!(sort("priority:desc", "last"), vec![0, 3, 2, 1]);
+        assert_eq!(sort("priority:asc", "first"), vec![1, 2, 0, 3]);
+        assert_eq!(sort("priority:desc", "first"), vec![1, 0, 3, 2]);
+        assert_eq!(sort("priority:asc", "drop"), vec![2, 0, 3]);
+        assert_eq!(sort("priority:asc", "unknown"), vec![2, 0, 3, 1]);
+    }
+
+    #[test]
+    fn multiple_sorts() {
+        assert_eq!(sort("priority:desc,date:asc", "last"), vec![3, 0, 2, 1]);
+        assert_eq!(
+            sort("priority:desc,relevance:asc", "last"),
+            vec![3, 0, 2, 1]
+        );
+        assert_eq!(sort("priority:desc,date:asc", "drop"), vec![3, 0]);
+        assert_eq!(sort("missing:asc,date:desc", "last"), vec![0, 3, 1, 2]);
+    }
+
+    #[test]
+    fn unknown_sorts() {
+        assert_eq!(sort("missing:asc", "drop"), vec![0, 1, 2, 3]);
+        assert_eq!(sort("priority", "drop"), vec![0, 1, 2, 3]);
+        assert_eq!(sort("relevance:asc", "last"), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn scores_follow_sorted_position() {
+        let mut results = results();
+        sorted_index().sort_results(&mut results, "date:asc", "last");
+        let scores: Vec<f32> = results.iter().map(|result| result.page_score).collect();
+        assert_eq!(scores, vec![4.0, 3.0, 2.0, 1.0]);
+    }
+}
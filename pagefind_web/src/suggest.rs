@@ -0,0 +1,182 @@
+use std::ops::Bound;
+
+use crate::search::{edit_distance, fuzzy_distance_for_term};
+use crate::SearchIndex;
+
+impl SearchIndex {
+    /// Proposes alternative queries for a search, replacing each word that doesn't
+    /// exist in the loaded index chunks with the closest words that do.
+    /// Returns no suggestions if every word of the query was found.
+    pub fn suggest(&self, query: &str, limit: usize) -> Vec<String> {
+        let stem = |word: &str| match &self.stemmer {
+            Some(stemmer) => stemmer.stem(word).into_owned(),
+            None => word.to_owned(),
+        };
+
+        let mut has_corrections = false;
+        let words: Vec<Vec<String>> = query
+            .split_whitespace()
+            .map(|word| {
+                let Some((prefix, core, suffix)) = split_query_syntax(word) else {
+                    return vec![word.to_owned()];
+                };
+                if core.contains(':') || self.stop_words.contains(core) {
+                    return vec![word.to_owned()];
+                }
+
+                let stemmed = stem(core);
+                let corrections = self.closest_words(&stemmed);
+                if corrections.is_empty() {
+                    return vec![word.to_owned()];
+                }
+                has_corrections = true;
+
+                // Indexed words are stemmed, so carry over whatever the stemmer
+                // removed from the search term, e.g. kubenetes -> kubernet(es)
+                let stemmed_suffix = core.strip_prefix(stemmed.as_str()).unwrap_or_default();
+                corrections
+                    .into_iter()
+                    .map(|correction| format!("{prefix}{correction}{stemmed_suffix}{suffix}"))
+                    .collect()
+            })
+            .collect();
+
+        if !has_corrections {
+            return vec![];
+        }
+
+        // The first suggestion uses the closest word for every correction,
+        // and each subsequent suggestion moves on to the next closest words.
+        let mut suggestions: Vec<String> = vec![];
+        let most_corrections = words.iter().map(Vec::len).max().unwrap_or_default();
+        for i in 0..most_corrections.min(limit) {
+            let suggestion = words
+                .iter()
+                .map(|options| options[i.min(options.len() - 1)].as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !suggestions.contains(&suggestion) {
+                suggestions.push(suggestion);
+            }
+        }
+        suggestions
+    }
+
+    /// Returns the loaded words closest to a stemmed term that has no matches of its own,
+    /// ordered by edit distance and then by how many pages contain them
+    fn closest_words(&self, term: &str) -> Vec<&str> {
+        let term_has_matches = self
+            .words
+            .range::<str, _>((Bound::Included(term), Bound::Unbounded))
+            .next()
+            .is_some_and(|(word, _)| word.starts_with(term));
+        let max_distance = fuzzy_distance_for_term(term, 2);
+        if term_has_matches || max_distance == 0 {
+            return vec![];
+        }
+
+        let mut candidates: Vec<(usize, usize, &str)> = self
+            .words
+            .iter()
+            .filter_map(|(word, pages)| {
+                edit_distance(term, word, max_distance)
+                    .map(|distance| (distance, pages.len(), word.as_str()))
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(b.2)));
+        candidates.into_iter().map(|(_, _, word)| word).collect()
+    }
+}
+
+/// Splits a word of a search query into any leading query syntax, the word itself,
+/// and any trailing query syntax. Returns None for words that are only query syntax.
+fn split_query_syntax(word: &str) -> Option<(&str, &str, &str)> {
+    if word == "OR" || word == "NOT" {
+        return None;
+    }
+    let start = word.len() - word.trim_start_matches(['-', '(', '"']).len();
+    // A closing quote may be followed by the phrase slop, e.g. runtime"~2
+    let end = word.find("\"~").unwrap_or(word.len()).max(start);
+    let end = start + word[start..end].trim_end_matches([')', '"']).len();
+    (start < end).then(|| (&word[..start], &word[start..end], &word[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PageWord;
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::empty(None);
+        for (word, pages) in [
+            ("rust", 3),
+            ("rask", 1),
+            ("rush", 1),
+            ("runtime", 2),
+            ("time", 1),
+            ("type", 2),
+        ] {
+            let pages = (0..pages)
+                .map(|page| PageWord {
+                    page,
+                    locs: vec![(0, 0)],
+                })
+                .collect();
+            index.words.insert(word.into(), pages);
+        }
+        index.stop_words.insert("thr".into());
+        index
+    }
+
+    #[test]
+    fn suggesting_closest_words() {
+        let index = index();
+        assert_eq!(index.suggest("rusk", 5), vec!["rust", "rask", "rush"]);
+        assert_eq!(index.suggest("rusk", 2), vec!["rust", "rask"]);
+        assert_eq!(index.suggest("runtmie", 5), vec!["runtime"]);
+        assert_eq!(index.suggest("rnutmie", 5), vec!["runtime"]);
+    }
+
+    #[test]
+    fn suggesting_multiple_words() {
+        let index = index();
+        assert_eq!(
+            index.suggest("rusk tyme", 5),
+            vec!["rust type", "rask time", "rush time"]
+        );
+        assert_eq!(
+            index.suggest("rust tyme", 5),
+            vec!["rust type", "rust time"]
+        );
+    }
+
+    #[test]
+    fn keeping_query_syntax() {
+        let index = index();
+        assert_eq!(
+            index.suggest("-rusk OR (\"tyme runtmie\"~2)", 1),
+            vec!["-rust OR (\"type runtime\"~2)"]
+        );
+        assert_eq!(index.suggest("title:rusk tyme", 1), vec!["title:rusk type"]);
+    }
+
+    #[test]
+    fn no_suggestions() {
+        let index = index();
+        assert!(index.suggest("rust run", 5).is_empty());
+        assert!(index.suggest("thr", 5).is_empty());
+        assert!(index.suggest("rz", 5).is_empty());
+        assert!(index.suggest("xyzzy", 5).is_empty());
+        assert!(index.suggest("rusk", 0).is_empty());
+    }
+
+    #[test]
+    fn splitting_query_syntax() {
+        assert_eq!(split_query_syntax("-word"), Some(("-", "word", "")));
+        assert_eq!(split_query_syntax("(\"word"), Some(("(\"", "word", "")));
+        assert_eq!(split_query_syntax("word\")"), Some(("", "word", "\")")));
+        assert_eq!(split_query_syntax("word\"~2)"), Some(("", "word", "\"~2)")));
+        assert_eq!(split_query_syntax("OR"), None);
+        assert_eq!(split_query_syntax("-(\""), None);
+    }
+}
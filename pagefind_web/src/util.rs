@@ -45,3 +45,10 @@ macro_rules! consume_inum {
     };
 }
 pub(crate) use consume_inum;
+
+macro_rules! consume_float {
+    ($decoder:ident) => {
+        $decoder.f64()?
+    };
+}
+pub(crate) use consume_float;
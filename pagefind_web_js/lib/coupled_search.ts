@@ -34,6 +34,7 @@ export class PagefindInstance {
   languages: Record<string, internal.PagefindEntryLanguage> | null;
   loadedLanguage?: string;
   includeCharacters?: string[];
+  indexMetaFields?: string[];
 
   version: string;
   loadedVersion?: string;
@@ -162,6 +163,14 @@ export class PagefindInstance {
       page_length: ranking.pageLength ?? null,
       term_saturation: ranking.termSaturation ?? null,
       term_frequency: ranking.termFrequency ?? null,
+      term_proximity: ranking.termProximity ?? null,
+      page_weight: ranking.pageWeight ?? null,
+      // Left undefined (and so out of the JSON) when not given, as null turns recency off
+      recency_sort: ranking.recency === null ? null : ranking.recency?.sort,
+      recency_meta: ranking.recency === null ? null : ranking.recency?.meta,
+      recency_decay: ranking.recency?.decay ?? null,
+      recency_half_life: ranking.recency?.halfLife ?? null,
+      recency_weight: ranking.recency?.weight ?? null,
     };
     let ptr = await this.getPtr();
     this.raw_ptr = this.backend.set_ranking_weights(
@@ -205,6 +214,7 @@ export class PagefindInstance {
       this.languages = entry_json.languages;
       this.loadedVersion = entry_json.version;
       this.includeCharacters = entry_json.include_characters ?? [];
+      this.indexMetaFields = entry_json.index_meta_fields ?? [];
       if (entry_json.version !== this.version) {
         if (this.primary) {
           console.warn(
@@ -424,28 +434,68 @@ export class PagefindInstance {
     return JSON.stringify(obj);
   }
 
-  stringifySorts(obj = {}) {
-    let sorts = Object.entries(obj);
-    // We currently only support one sort directive,
-    // so we'll grab the first sort provided in the object.
-    for (let [sort, direction] of sorts) {
-      if (sorts.length > 1) {
-        console.warn(
-          `Pagefind was provided multiple sort options in this search, but can only operate on one. Using the ${sort} sort.`,
-        );
+  stringifySorts(obj: Object | string = {}) {
+    // Sorts can also be written as a string, e.g. "priority desc, date desc, relevance"
+    let sorts =
+      typeof obj === "string"
+        ? obj
+            .split(",")
+            .map((sort) => sort.trim().split(/\s+/))
+            .filter(([sort]) => sort)
+            .map(([sort, direction]) => [
+              sort,
+              direction ?? (sort === "relevance" ? "desc" : "asc"),
+            ])
+        : Object.entries(obj);
+
+    // Each sort breaks the ties of the sorts before it
+    return sorts
+      .map(([sort, direction]) => {
+        if (direction !== "asc" && direction !== "desc") {
+          console.warn(
+            `Pagefind was provided a sort with unknown direction ${direction}. Supported: [asc, desc]`,
+          );
+        }
+        return `${sort}:${direction}`;
+      })
+      .join(",");
+  }
+
+  /** Strips query syntax from a search term, leaving the words that should be highlighted */
+  stripQuerySyntax(term: string) {
+    const words: string[] = [];
+    let negated = false;
+    for (const word of term
+      .replace(/"~\d+/g, " ")
+      .replace(/["()]/g, " ")
+      .split(/\s+/)) {
+      if (!word || word === "OR") continue;
+      if (word === "NOT") {
+        negated = true;
+        continue;
       }
-      if (direction !== "asc" && direction !== "desc") {
-        console.warn(
-          `Pagefind was provided a sort with unknown direction ${direction}. Supported: [asc, desc]`,
+      if (!negated && !word.startsWith("-")) {
+        const [field, ...field_term] = word.split(":");
+        words.push(
+          field_term.length && this.indexMetaFields?.includes(field)
+            ? field_term.join(":")
+            : word,
         );
       }
-      return `${sort}:${direction}`;
+      negated = false;
     }
+    return words.join(" ");
+  }
 
-    return ``;
+  fuzzyDistance(fuzzy: PagefindSearchOptions["fuzzy"]) {
+    if (fuzzy === true) return 2;
+    if (typeof fuzzy === "number" && fuzzy > 0) {
+      return Math.min(Math.floor(fuzzy), 255);
+    }
+    return 0;
   }
 
-  async filters() {
+  async loadAllFilters() {
     let ptr = await this.getPtr();
 
     let filters = this.backend.request_all_filter_indexes(ptr) as string;
@@ -458,12 +508,23 @@ export class PagefindInstance {
     }
 
     // pointer may have updated from the loadChunk calls
-    ptr = await this.getPtr();
+    return await this.getPtr();
+  }
+
+  async filters() {
+    let ptr = await this.loadAllFilters();
 
     let results = this.backend.filters(ptr) as string;
     return JSON.parse(results) as PagefindFilterCounts;
   }
 
+  async nestedFilters() {
+    let ptr = await this.loadAllFilters();
+
+    let results = this.backend.nested_filters(ptr) as string;
+    return JSON.parse(results) as PagefindNestedFilters;
+  }
+
   async preload(term: string, options: PagefindSearchOptions = {}) {
     await this.search(term, {
       ...options,
@@ -489,65 +550,34 @@ export class PagefindInstance {
     let ptr = await this.getPtr();
     let filter_only = term === null;
     term = term ?? "";
-    let exact_search = /^\s*".+"\s*$/.test(term);
+    // Queries are normalized in the WebAssembly, so that exact searches, phrase slop,
+    // and query syntax are read the same way as the native search
+    const normalized: internal.PagefindNormalizedQuery = JSON.parse(
+      this.backend.normalize_query(
+        term,
+        (this.includeCharacters ?? []).join(""),
+        JSON.stringify(this.indexMetaFields ?? []),
+      ),
+    );
+    let exact_search = normalized.exact;
+    // Quoted searches can allow other words between the terms of the phrase, e.g. "async runtime"~2
+    let phrase_slop = normalized.phrase_slop;
     if (exact_search) {
-      log(`Running an exact search`);
+      log(`Running an exact search with a slop of ${phrase_slop}`);
     }
-
-    let trueLanguage: string | null = null;
-    try {
-      trueLanguage = Intl.getCanonicalLocales(this.loadedLanguage)[0];
-    } catch (err) {
-      // Loaded language is not valid
-    }
-    const term_chunks: string[] = [];
-    let segments: string[];
-
-    // TODO: resolve type error for Intl.Segmenter
-    //@ts-ignore: Property 'Segmenter' does not exist on type 'typeof Intl'
-    if (trueLanguage && typeof Intl.Segmenter !== "undefined") {
-      //@ts-ignore: Property 'Segmenter' does not exist on type 'typeof Intl'
-      const segmenter = new Intl.Segmenter(trueLanguage, {
-        granularity: "grapheme",
-      });
-      segments = [...segmenter.segment(term)].map(
-        ({ segment }: { segment: string }) => segment,
-      );
-    } else {
-      segments = [...term];
-    }
-
-    for (const segment of segments) {
-      if (this.includeCharacters?.includes(segment)) {
-        term_chunks.push(segment);
-      } else if (
-        !/^\p{Pd}|\p{Pe}|\p{Pf}|\p{Pi}|\p{Po}|\p{Ps}$/u.test(segment)
-      ) {
-        term_chunks.push(segment.toLocaleLowerCase());
-      }
-
-      /**
-       * Notes:
-       * Regex to match the Rust \w class if we need it:
-       * /^\p{Pc}|\p{LC}|\p{Ll}|\p{Lm}|\p{Lo}|\p{Lt}|\p{Lu}|\p{Nd}|\p{Nl}|\p{No}|\s$/u
-       * ES2024 regex to match emoji if we need it:
-       * /\p{RGI_Emoji}/v
-       */
+    // Exact searches never match fuzzily
+    let fuzzy_distance = exact_search ? 0 : this.fuzzyDistance(options.fuzzy);
+    if (fuzzy_distance > 0) {
+      log(`Allowing fuzzy matches up to ${fuzzy_distance} edit(s) away`);
     }
 
-    // TODO: We could use the "word" granularity for Intl.Segmenter to handle
-    // segmentation for non-whitespace-delimited languages.
-
-    term = term_chunks
-      .join("")
-      .replace(/\s{2,}/g, " ")
-      .trim();
-
+    term = normalized.query;
     log(`Normalized search term to ${term}`);
 
     if (!term?.length && !filter_only) {
       return {
         results: [],
+        resultCount: 0,
         unfilteredResultCount: 0,
         filters: {},
         totalFilters: {},
@@ -565,7 +595,11 @@ export class PagefindInstance {
     const filter_list = this.stringifyFilters(options.filters);
     log(`Stringified filters to ${filter_list}`);
 
-    let index_resp = this.backend.request_indexes(ptr, term) as string;
+    let index_resp = this.backend.request_indexes(
+      ptr,
+      term,
+      fuzzy_distance > 0,
+    ) as string;
     let index_array: string[] = JSON.parse(index_resp);
     let filter_resp = this.backend.request_filter_indexes(
       ptr,
@@ -595,15 +629,24 @@ export class PagefindInstance {
       term,
       filter_list,
       sort_list,
+      options.sortMissing ?? "last",
       exact_search,
+      phrase_slop,
+      fuzzy_distance,
+      options.offset ?? 0,
+      options.limit,
+      options.countsOnly ?? false,
     ) as string;
     log(`Got the raw search result: ${result}`);
 
+    const highlight_term = this.stripQuerySyntax(term);
+
     let {
       filtered_counts,
       total_counts,
       results,
       unfiltered_total,
+      filtered_total,
       search_keywords,
     }: internal.PagefindSearchResponse = JSON.parse(result);
 
@@ -631,7 +674,7 @@ export class PagefindInstance {
         score: result.s * this.indexWeight,
         words: locations,
         data: async () =>
-          await this.loadFragment(result.p, weighted_locations, term),
+          await this.loadFragment(result.p, weighted_locations, highlight_term),
       };
 
       if (result.params) {
@@ -667,10 +710,11 @@ export class PagefindInstance {
     const realTime = Date.now() - start;
 
     log(
-      `Found ${results.length} result${results.length == 1 ? "" : "s"} for "${term}" in ${Date.now() - searchStart}ms (${Date.now() - start}ms realtime)`,
+      `Found ${filtered_total} result${filtered_total == 1 ? "" : "s"} for "${term}" in ${Date.now() - searchStart}ms (${Date.now() - start}ms realtime)`,
     );
     let response: PagefindSearchResults = {
       results: resultsInterface,
+      resultCount: filtered_total,
       unfilteredResultCount: unfiltered_total,
       filters: filtered_counts,
       totalFilters: total_counts,
@@ -685,8 +729,56 @@ export class PagefindInstance {
       response.search_keywords = search_keywords;
     }
 
+    if (!filtered_total && term) {
+      response.suggestions = await this.suggest(term);
+      log(`Suggesting ${JSON.stringify(response.suggestions)} instead`);
+    }
+
     return response;
   }
+
+  async complete(
+    prefix: string,
+    limit: number = 5,
+  ): Promise<PagefindCompletion[]> {
+    // Only the last word of the input is completed
+    prefix = prefix.toLocaleLowerCase().split(/\s+/).pop() ?? "";
+    if (!prefix.length) return [];
+
+    let ptr = await this.getPtr();
+    let index_resp = this.backend.request_completion_indexes(
+      ptr,
+      prefix,
+    ) as string;
+    let index_array: string[] = JSON.parse(index_resp);
+    await Promise.all(
+      index_array.filter((v) => v).map((chunk) => this.loadChunk(chunk)),
+    );
+
+    // pointer may have updated from the loadChunk calls
+    ptr = await this.getPtr();
+    let completions = this.backend.complete(ptr, prefix, limit) as string;
+    return JSON.parse(completions);
+  }
+
+  /**
+   * Proposes alternative queries for a search term by replacing unknown words
+   * with the closest words in the index, for showing "Did you mean" prompts.
+   */
+  async suggest(term: string, limit: number = 3): Promise<string[]> {
+    let ptr = await this.getPtr();
+    // Load any chunks that may contain words close to the search terms
+    let index_resp = this.backend.request_indexes(ptr, term, true) as string;
+    let index_array: string[] = JSON.parse(index_resp);
+    await Promise.all(
+      index_array.filter((v) => v).map((chunk) => this.loadChunk(chunk)),
+    );
+
+    // pointer may have updated from the loadChunk calls
+    ptr = await this.getPtr();
+    let suggestions = this.backend.suggest(ptr, term, limit) as string;
+    return JSON.parse(suggestions);
+  }
 }
 
 export class Pagefind {
@@ -779,15 +871,62 @@ export class Pagefind {
     return merged;
   }
 
+  mergeNestedFilters(filters: PagefindNestedFilters[]) {
+    const merged: PagefindNestedFilters = {};
+    for (const instanceFilters of filters) {
+      for (const [filterKey, values] of Object.entries(instanceFilters)) {
+        merged[filterKey] = merged[filterKey] || {};
+        this.mergeNestedFilterValues(merged[filterKey], values);
+      }
+    }
+    return merged;
+  }
+
+  mergeNestedFilterValues(
+    filter: Record<string, PagefindNestedFilterCount>,
+    values: Record<string, PagefindNestedFilterCount>,
+  ) {
+    for (const [valueKey, { count, children }] of Object.entries(values)) {
+      filter[valueKey] = filter[valueKey] || { count: 0, children: {} };
+      filter[valueKey].count += count;
+      this.mergeNestedFilterValues(filter[valueKey].children, children);
+    }
+  }
+
   async filters() {
     let filters = await Promise.all(this.instances.map((i) => i.filters()));
     return this.mergeFilters(filters);
   }
 
+  async nestedFilters() {
+    let filters = await Promise.all(
+      this.instances.map((i) => i.nestedFilters()),
+    );
+    return this.mergeNestedFilters(filters);
+  }
+
   async preload(term: string, options = {}) {
     await Promise.all(this.instances.map((i) => i.preload(term, options)));
   }
 
+  async complete(
+    prefix: string,
+    limit: number = 5,
+  ): Promise<PagefindCompletion[]> {
+    const completions = await Promise.all(
+      this.instances.map((i) => i.complete(prefix, limit)),
+    );
+
+    const merged = new Map<string, number>();
+    for (const { word, count } of completions.flat()) {
+      merged.set(word, (merged.get(word) ?? 0) + count);
+    }
+    return [...merged.entries()]
+      .map(([word, count]) => ({ word, count }))
+      .sort((a, b) => b.count - a.count || a.word.localeCompare(b.word))
+      .slice(0, limit);
+  }
+
   async debouncedSearch(
     term: string,
     options?: PagefindSearchOptions,
@@ -812,18 +951,38 @@ export class Pagefind {
     term: string,
     options: PagefindSearchOptions = {},
   ): Promise<PagefindIndexesSearchResults> {
+    // Results from multiple instances are merged before paginating,
+    // so each instance needs to return every result up to the end of the page
+    const { offset = 0, limit } = options;
+    const paginate = this.instances.length > 1;
+    const instanceOptions = paginate
+      ? {
+          ...options,
+          offset: 0,
+          limit: limit === undefined ? limit : offset + limit,
+        }
+      : options;
+
     let search = await Promise.all(
       this.instances.map(
-        (i) => i.search(term, options) as Promise<PagefindSearchResults>,
+        (i) =>
+          i.search(term, instanceOptions) as Promise<PagefindSearchResults>,
       ),
     );
 
     const filters = this.mergeFilters(search.map((s) => s.filters));
     const totalFilters = this.mergeFilters(search.map((s) => s.totalFilters));
-    const results = search
+    let results = search
       .map((s) => s.results)
       .flat()
       .sort((a, b) => b.score - a.score);
+    if (paginate) {
+      results = results.slice(
+        offset,
+        limit === undefined ? undefined : offset + limit,
+      );
+    }
+    const resultCount = search.reduce((sum, s) => sum + s.resultCount, 0);
     const timings = search.map((s) => s.timings);
     const unfilteredResultCount = search.reduce(
       (sum, s) => sum + s.unfilteredResultCount,
@@ -832,6 +991,7 @@ export class Pagefind {
 
     let response: PagefindIndexesSearchResults = {
       results,
+      resultCount,
       unfilteredResultCount,
       filters,
       totalFilters,
@@ -842,6 +1002,15 @@ export class Pagefind {
       response.search_keywords = search[0].search_keywords;
     }
 
+    if (!resultCount) {
+      const suggestions = [
+        ...new Set(search.flatMap((s) => s.suggestions ?? [])),
+      ];
+      if (suggestions.length) {
+        response.suggestions = suggestions;
+      }
+    }
+
     return response;
   }
 }
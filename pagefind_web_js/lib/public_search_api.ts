@@ -47,7 +47,15 @@ export const preload = async (term: string, options: PagefindSearchOptions) => {
   init_pagefind();
   return await pagefind!.preload(term, options);
 };
+export const complete = async (prefix: string, limit?: number) => {
+  init_pagefind();
+  return await pagefind!.complete(prefix, limit);
+};
 export const filters = async () => {
   init_pagefind();
   return await pagefind!.filters();
 };
+export const nestedFilters = async () => {
+  init_pagefind();
+  return await pagefind!.nestedFilters();
+};
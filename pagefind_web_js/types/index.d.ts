@@ -67,6 +67,39 @@ declare global {
             Reducing this number is a good way to boost longer documents in your search results, as they no longer get penalized for having a low term frequency.
          */
     termFrequency?: Number;
+    /**
+            Controls how much ranking favours pages where the search terms appear close together.
+            Minimum value is 0.0, where the distance between search terms has no effect on the ranking.
+            Increasing this number boosts pages where the terms appear next to each other,
+            e.g. at 1.0 a page containing the terms side by side has its score doubled.
+         */
+    termProximity?: Number;
+    /**
+            Controls how much the page weight set by `data-pagefind-page-weight` affects ranking.
+            At 1.0, a page with a page weight of 2.0 has its score doubled.
+            Minimum value is 0.0, where page weights have no effect on the ranking.
+         */
+    pageWeight?: Number;
+    /**
+            Boosts recent pages, using the dates of a date sort or a metadata field.
+            Pages are aged against the newest page, and pages without a date are not boosted.
+            Set to `null` to turn recency off again.
+         */
+    recency?: {
+      /** The sort key whose dates should be used, e.g. the `date` in `data-pagefind-sort="date"`. `null` or `""` turns recency off */
+      sort?: string | null;
+      /** The metadata field whose dates should be used, if not using a sort. `null` or `""` turns recency off */
+      meta?: string | null;
+      /** How quickly the boost falls away as pages get older. Defaults to `exponential`. */
+      decay?: "exponential" | "linear" | "gauss";
+      /** The age in days at which a page receives half of the boost. Defaults to 365. */
+      halfLife?: Number;
+      /**
+            Controls how much recency affects ranking. Defaults to 1.0, where the newest page has its score doubled.
+            Minimum value is 0.0, where the date of a page has no effect on the ranking.
+           */
+      weight?: Number;
+    } | null;
   };
 
   /** Options that can be passed to pagefind.search() */
@@ -77,17 +110,68 @@ declare global {
     verbose?: boolean;
     /** The set of filters to execute with this search. Input type is extremely flexible, see the filtering docs for details */
     filters?: Object;
-    /** The set of sorts to use for this search, instead of relevancy */
-    sort?: Object;
+    /**
+     * The set of sorts to use for this search, instead of relevancy.
+     * Each sort breaks the ties of the sorts before it, and `relevance` can be used as a sort.
+     * @example { priority: "desc", date: "desc", relevance: "desc" }
+     * @example "priority desc, date desc, relevance"
+     */
+    sort?: Object | string;
+    /**
+     * Where to place results that aren't tagged with a sort.
+     * `last` and `first` keep these results at the end or start regardless of the sort direction,
+     * and `drop` removes them from the results.
+     * Defaults to `last`.
+     */
+    sortMissing?: "drop" | "first" | "last";
+    /**
+     * Also match words that are a small number of typos away from the search terms.
+     *
+     * `true` allows up to two edits, or a number can be given to set the maximum edit distance.
+     * Short search terms are allowed fewer edits, and fuzzy matches rank below exact and prefix matches.
+     */
+    fuzzy?: boolean | number;
+    /** How many results to skip, for paginating through results. Defaults to 0. */
+    offset?: number;
+    /** The maximum number of results to return, after skipping `offset` results. Defaults to returning all results. */
+    limit?: number;
+    /** Return the result and filter counts without any results, for when only the counts are displayed */
+    countsOnly?: boolean;
+  };
+
+  /** A completion returned from pagefind.complete() */
+  type PagefindCompletion = {
+    /** An indexed word starting with the given prefix, in the form it was most often written on the site, e.g. `kubernetes` */
+    word: string;
+    /** How many pages contain this word */
+    count: number;
   };
 
   /** Filter counts returned from pagefind.filters(), and alongside results from pagefind.search() */
   type PagefindFilterCounts = Record<string, Record<string, number>>;
 
+  /** The page count of a nested filter value, including the pages of every value nested beneath it */
+  type PagefindNestedFilterCount = {
+    count: number;
+    /** The values directly beneath this value, keyed by their full value, e.g. `Guides > Deployment` */
+    children: Record<string, PagefindNestedFilterCount>;
+  };
+
+  /**
+   * Filters returned from pagefind.nestedFilters(). Values of filters indexed with a `filter_separator`
+   * are nested beneath their parent value, starting from the top-level values.
+   */
+  type PagefindNestedFilters = Record<
+    string,
+    Record<string, PagefindNestedFilterCount>
+  >;
+
   /** The main results object returned from a call to pagefind.search() */
   type PagefindSearchResults = {
-    /** All pages that match the search query and filters provided */
+    /** All pages that match the search query and filters provided, within the `offset` and `limit` given */
     results: PagefindSearchResult[];
+    /** How many results matched the search query and filters provided, before applying the `offset` and `limit` */
+    resultCount: number;
     /** How many results would there have been if you had omitted the filters */
     unfilteredResultCount: number;
     /** Given the query and filters provided, how many remaining results are there under each filter? */
@@ -102,12 +186,16 @@ declare global {
     };
     /** Verbose information on stemming returned in the Pagefind Playground */
     search_keywords?: string[];
+    /** When there are no results, alternative queries using the closest words in the index */
+    suggestions?: string[];
   };
 
   /** The main results object returned from a call to pagefind.search() */
   type PagefindIndexesSearchResults = {
-    /** All pages that match the search query and filters provided */
+    /** All pages that match the search query and filters provided, within the `offset` and `limit` given */
     results: PagefindSearchResult[];
+    /** How many results matched the search query and filters provided, before applying the `offset` and `limit` */
+    resultCount: number;
     /** How many results would there have been if you had omitted the filters */
     unfilteredResultCount: number;
     /** Given the query and filters provided, how many remaining results are there under each filter? */
@@ -122,6 +210,8 @@ declare global {
     }[];
     /** Verbose information on stemming returned in the Pagefind Playground */
     search_keywords?: string[];
+    /** When there are no results, alternative queries using the closest words in the index */
+    suggestions?: string[];
   };
 
   /** A single result from a search query, before actual data has been loaded */
@@ -4,6 +4,7 @@ export type PagefindEntryJson = {
   version: string;
   languages: Record<string, PagefindEntryLanguage>;
   include_characters: string[];
+  index_meta_fields?: string[];
 };
 
 export type PagefindEntryLanguage = {
@@ -12,10 +13,17 @@ export type PagefindEntryLanguage = {
   page_count: number;
 };
 
+export type PagefindNormalizedQuery = {
+  query: string;
+  exact: boolean;
+  phrase_slop: number;
+};
+
 export type PagefindSearchResponse = {
   filtered_counts: PagefindFilterCounts;
   total_counts: PagefindFilterCounts;
   unfiltered_total: number;
+  filtered_total: number;
   results: PagefindSearchResponseResult[];
   search_keywords?: string[];
 };
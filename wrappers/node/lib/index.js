@@ -54,6 +54,10 @@ export const createIndex = (config) => new Promise((resolve, reject) => {
                 root_selector: config?.rootSelector,
                 exclude_selectors: config?.excludeSelectors,
                 force_language: config?.forceLanguage,
+                index_meta_fields: config?.indexMetaFields,
+                filter_separator: config?.filterSeparator,
+                sort_date_formats: config?.sortDateFormats,
+                recency_meta_fields: config?.recencyMetaFields,
                 verbose: config?.verbose,
                 logfile: config?.logfile,
                 keep_index_url: config?.keepIndexUrl,
@@ -31,6 +31,28 @@ export interface PagefindServiceConfig {
      * Expects an ISO 639-1 code.
      */
     forceLanguage?: string,
+    /**
+     * Metadata fields that should be indexed as their own searchable fields, e.g. "title".
+     * These fields can then be searched using the `title:term` syntax.
+     */
+    indexMetaFields?: string[],
+    /**
+     * Split filter values into nested levels on this separator, e.g. " > ".
+     * Filtering to a level includes every level nested beneath it.
+     */
+    filterSeparator?: string,
+    /**
+     * Date formats to recognize in sort values, in addition to ISO 8601 dates.
+     * Defaults to `["%B %d, %Y", "%d %B %Y"]`.
+     * @example ["%d/%m/%Y"]
+     */
+    sortDateFormats?: string[],
+    /**
+     * Metadata fields holding dates that search results can be boosted by how recent they are,
+     * using the `recency` ranking parameter.
+     * @example ["published"]
+     */
+    recencyMetaFields?: string[],
     /**
      * Print verbose logging while indexing the site. Does not impact the web-facing search.
      * When running as a service, only impacts the logfile (if present).
@@ -29,6 +29,10 @@ export interface InternalPagefindServiceConfig {
     root_selector?: string,
     exclude_selectors?: string[],
     force_language?: string,
+    index_meta_fields?: string[],
+    filter_separator?: string,
+    sort_date_formats?: string[],
+    recency_meta_fields?: string[],
     verbose?: boolean,
     logfile?: string,
     keep_index_url?: boolean,
@@ -32,6 +32,26 @@ class IndexConfig(TypedDict, total=False):
     Ignores any detected languages and creates a single index for the entire site as the
     provided language. Expects an ISO 639-1 code, such as ``en`` or ``pt``.
     """
+    index_meta_fields: Optional[Sequence[str]]
+    """
+    Metadata fields that should be indexed as their own searchable fields, such as ``title``.
+    These fields can then be searched using the ``title:term`` syntax.
+    """
+    filter_separator: Optional[str]
+    """
+    Splits filter values into nested levels on this separator, such as `` > ``.
+    Filtering to a level includes every level nested beneath it.
+    """
+    sort_date_formats: Optional[Sequence[str]]
+    """
+    Date formats to recognize in sort values, in addition to ISO 8601 dates, such as ``%d/%m/%Y``.
+    Defaults to ``%B %d, %Y`` and ``%d %B %Y``.
+    """
+    recency_meta_fields: Optional[Sequence[str]]
+    """
+    Metadata fields holding dates, such as ``published``, that search results can be boosted
+    by how recent they are, using the ``recency`` ranking parameter.
+    """
     verbose: Optional[bool]
     """
     Prints extra logging while indexing the site. Only affects the CLI, does not impact
@@ -17,6 +17,10 @@ class InternalPagefindServiceConfig(TypedDict, total=False):
     root_selector: Optional[str]
     exclude_selectors: Optional[Sequence[str]]
     force_language: Optional[str]
+    index_meta_fields: Optional[Sequence[str]]
+    filter_separator: Optional[str]
+    sort_date_formats: Optional[Sequence[str]]
+    recency_meta_fields: Optional[Sequence[str]]
     verbose: Optional[bool]
     logfile: Optional[str]
     keep_index_url: Optional[bool]